@@ -0,0 +1,2 @@
+-- Replays of new scores are stored as JSON
+ALTER TABLE score ADD COLUMN replay TEXT;
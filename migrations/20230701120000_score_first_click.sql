@@ -0,0 +1,5 @@
+-- Scores are ranked separately for each first-click policy:
+-- 0 unprotected, 1 safe cell, 2 safe opening, 3 no guessing
+ALTER TABLE score ADD COLUMN first_click INTEGER NOT NULL DEFAULT 1;
+
+UPDATE score SET first_click = 3 WHERE json_extract(replay, '$.no_guess') = 1;
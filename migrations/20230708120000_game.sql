@@ -0,0 +1,16 @@
+-- Every finished game, won or lost, for player statistics
+CREATE TABLE game (
+    id INTEGER PRIMARY KEY,
+    player_id INTEGER NOT NULL,
+    difficulty_id INTEGER NOT NULL,
+    first_click INTEGER NOT NULL,
+    won BOOLEAN NOT NULL,
+    time INTEGER NOT NULL,
+    bbbv INTEGER NOT NULL,
+    clicks INTEGER NOT NULL,
+    finished_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (player_id) REFERENCES player (id),
+    FOREIGN KEY (difficulty_id) REFERENCES difficulty (id)
+);
+
+CREATE INDEX game_player_id ON game (player_id);
@@ -0,0 +1,6 @@
+-- Efficiency metrics of new scores; 3BV/s and IOE follow from these and the time
+ALTER TABLE score ADD COLUMN bbbv INTEGER;
+ALTER TABLE score ADD COLUMN zini INTEGER;
+ALTER TABLE score ADD COLUMN left_clicks INTEGER;
+ALTER TABLE score ADD COLUMN right_clicks INTEGER;
+ALTER TABLE score ADD COLUMN chord_clicks INTEGER;
@@ -0,0 +1,7 @@
+-- Times in milliseconds from the first click to the last. Scores with a
+-- replay get their exact time back from it; the rest only had whole seconds.
+ALTER TABLE score ADD COLUMN time_ms INTEGER NOT NULL DEFAULT 0;
+UPDATE score SET time_ms = COALESCE(json_extract(replay, '$.events[#-1].time'), time * 1000);
+
+ALTER TABLE game ADD COLUMN time_ms INTEGER NOT NULL DEFAULT 0;
+UPDATE game SET time_ms = time * 1000;
@@ -0,0 +1,10 @@
+-- Players log in with a password. Names from before accounts have no
+-- password until their owner claims them.
+ALTER TABLE player ADD COLUMN password_hash TEXT;
+
+CREATE TABLE login_session (
+    token TEXT PRIMARY KEY,
+    player_id INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (player_id) REFERENCES player (id)
+);
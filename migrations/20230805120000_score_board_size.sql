@@ -0,0 +1,10 @@
+-- Board dimensions of each score, so custom boards can be ranked by size.
+-- Scores from before this were all on the standard difficulties.
+ALTER TABLE score ADD COLUMN width INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE score ADD COLUMN height INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE score ADD COLUMN mine_count INTEGER NOT NULL DEFAULT 0;
+UPDATE score SET width = 9, height = 9, mine_count = 10 WHERE difficulty_id = 1;
+UPDATE score SET width = 16, height = 16, mine_count = 40 WHERE difficulty_id = 2;
+UPDATE score SET width = 30, height = 16, mine_count = 99 WHERE difficulty_id = 3;
+
+CREATE INDEX score_board_size ON score (difficulty_id, width, height, mine_count);
@@ -0,0 +1,18 @@
+-- One board a day, dealt from the day's seed to everyone.
+CREATE TABLE daily_challenge (
+    date TEXT PRIMARY KEY,
+    seed INTEGER NOT NULL
+);
+
+-- A player gets one ranked attempt at each day's board.
+CREATE TABLE daily_attempt (
+    player_id INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    PRIMARY KEY (player_id, date),
+    FOREIGN KEY (player_id) REFERENCES player (id),
+    FOREIGN KEY (date) REFERENCES daily_challenge (date)
+);
+
+-- Scores on a daily board, which stay off the regular leaderboards.
+ALTER TABLE score ADD COLUMN challenge_date TEXT;
+CREATE INDEX score_challenge_date ON score (challenge_date);
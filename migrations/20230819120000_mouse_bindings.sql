@@ -0,0 +1,2 @@
+-- Each player's mouse bindings as JSON, so they follow them between browsers.
+ALTER TABLE player ADD COLUMN mouse_bindings TEXT;
@@ -0,0 +1,11 @@
+-- One-time codes for claiming a name from before accounts. An admin who
+-- knows who a name belongs to hands them a code, e.g.
+--   INSERT INTO name_claim (code, player_id)
+--   SELECT lower(hex(randomblob(16))), id FROM player WHERE name = 'someone'
+--   RETURNING code;
+CREATE TABLE name_claim (
+    code TEXT PRIMARY KEY,
+    player_id INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (player_id) REFERENCES player (id)
+);
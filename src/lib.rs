@@ -26,13 +26,29 @@ cfg_if! {
 
         pub struct AppState {
             pub db_pool: Pool<Sqlite>,
+            pub sessions: ui::GameSessions,
         }
 
         pub fn register_server_functions() {
             use leptos::ServerFn;
 
-            _ = ui::SaveScore::register();
-            _ = ui::GetScores::register();
+            _ = ui::GetLeaderboard::register();
+            _ = ui::GetCustomBoards::register();
+            _ = ui::NewDailySession::register();
+            _ = ui::GetDailyLeaderboard::register();
+            _ = ui::GetReplay::register();
+            _ = ui::NewSession::register();
+            _ = ui::SessionAction::register();
+            _ = ui::SubmitSession::register();
+            _ = ui::RecordGame::register();
+            _ = ui::GetPlayerStats::register();
+            _ = ui::CurrentPlayer::register();
+            _ = ui::Register::register();
+            _ = ui::ClaimName::register();
+            _ = ui::Login::register();
+            _ = ui::Logout::register();
+            _ = ui::GetMouseBindings::register();
+            _ = ui::SaveMouseBindings::register();
         }
     }
 }
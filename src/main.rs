@@ -24,14 +24,18 @@ async fn main() -> std::io::Result<()> {
 
     register_server_functions();
 
+    // Created once so every worker sees the same game sessions.
+    let app_state = web::Data::new(AppState {
+        db_pool: pool,
+        sessions: Default::default(),
+    });
+
     HttpServer::new(move || {
         let leptos_options = &conf.leptos_options;
         let site_root = &leptos_options.site_root;
 
         App::new()
-            .app_data(web::Data::new(AppState {
-                db_pool: pool.clone(),
-            }))
+            .app_data(app_state.clone())
             .route("/api/{tail:.*}", leptos_actix::handle_server_fns())
             .leptos_routes(
                 leptos_options.to_owned(),
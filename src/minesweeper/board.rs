@@ -21,6 +21,7 @@ impl Display for BoardError {
     }
 }
 
+#[derive(Clone, PartialEq)]
 pub struct Board {
     b: Vec<Vec<CellKind>>,
     pub height: u8,
@@ -1,19 +1,68 @@
 use super::{pos::Pos, GameState};
+use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+/// What the player has put on a closed cell.
+#[derive(Debug, Default, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Mark {
+    #[default]
+    None,
+    Flag,
+    /// Unsure. Counts for nothing: the cell opens and chords like an
+    /// unmarked one.
+    Question,
+}
+
+impl Mark {
+    /// The mark after flagging a cell marked with this one. Question marks
+    /// come between the flag and no mark when `question_marks` is on.
+    pub fn next(self, question_marks: bool) -> Self {
+        match self {
+            Mark::None => Mark::Flag,
+            Mark::Flag if question_marks => Mark::Question,
+            Mark::Flag | Mark::Question => Mark::None,
+        }
+    }
+}
+
+/// Reads a mark, or the `flagged` bool closed cells had before question
+/// marks, so games saved back then still load.
+fn mark_or_flagged<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Mark, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Mark(Mark),
+        Flagged(bool),
+    }
+
+    Ok(match Stored::deserialize(deserializer)? {
+        Stored::Mark(mark) => mark,
+        Stored::Flagged(true) => Mark::Flag,
+        Stored::Flagged(false) => Mark::None,
+    })
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum CellKind {
-    Open { neighbor_mines: u8 },
-    Closed { flagged: bool },
-    Mine { flagged: bool },
+    Open {
+        neighbor_mines: u8,
+    },
+    Closed {
+        #[serde(alias = "flagged", deserialize_with = "mark_or_flagged")]
+        mark: Mark,
+    },
+    Mine {
+        #[serde(alias = "flagged", deserialize_with = "mark_or_flagged")]
+        mark: Mark,
+    },
 }
 
 impl CellKind {
     pub fn new_closed() -> Self {
-        CellKind::Closed { flagged: false }
+        CellKind::Closed { mark: Mark::None }
     }
 
     pub fn new_mine() -> Self {
-        CellKind::Mine { flagged: false }
+        CellKind::Mine { mark: Mark::None }
     }
 
     pub fn new_open(count: u8) -> Self {
@@ -22,14 +71,17 @@ impl CellKind {
         }
     }
 
-    pub fn is_flagged(&self) -> bool {
+    pub fn mark(&self) -> Mark {
         match self {
-            CellKind::Open { .. } => false,
-            CellKind::Closed { flagged } => *flagged,
-            CellKind::Mine { flagged } => *flagged,
+            CellKind::Open { .. } => Mark::None,
+            CellKind::Closed { mark } | CellKind::Mine { mark } => *mark,
         }
     }
 
+    pub fn is_flagged(&self) -> bool {
+        self.mark() == Mark::Flag
+    }
+
     pub fn is_mine(&self) -> bool {
         matches!(self, Self::Mine { .. })
     }
@@ -62,11 +114,14 @@ impl CellKind {
         if self.is_flagged() {
             return ("🚩", "flagged");
         }
+        let question = self.mark() == Mark::Question;
         match self {
+            CellKind::Closed { .. } if question => ("❓", "closed question"),
             CellKind::Closed { .. } => ("", "closed"),
             CellKind::Mine { .. } => match state {
                 GameState::Lose => ("💣", "mine"),
                 GameState::Win => ("🚩", "flagged"),
+                GameState::Playing | GameState::Unstarted if question => ("❓", "closed question"),
                 GameState::Playing | GameState::Unstarted => ("", "closed"),
             },
             CellKind::Open {
@@ -85,6 +140,29 @@ impl CellKind {
             },
         }
     }
+
+    /// What a screen reader says the cell is. Mines are only told apart once
+    /// the game is over.
+    pub fn describe(&self, state: GameState) -> String {
+        if self.is_flagged() {
+            return "flagged".into();
+        }
+        let unopened = match self.mark() {
+            Mark::Question => "unopened, question mark",
+            _ => "unopened",
+        };
+        match self {
+            CellKind::Closed { .. } => unopened.into(),
+            CellKind::Mine { .. } => match state {
+                GameState::Lose => "mine".into(),
+                GameState::Win => "flagged".into(),
+                GameState::Playing | GameState::Unstarted => unopened.into(),
+            },
+            CellKind::Open { neighbor_mines: 0 } => "no adjacent mines".into(),
+            CellKind::Open { neighbor_mines: 1 } => "1 adjacent mine".into(),
+            CellKind::Open { neighbor_mines } => format!("{} adjacent mines", neighbor_mines),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -93,6 +171,8 @@ pub struct Cell {
     pub kind: CellKind,
     pub icon: String,
     pub class: String,
+    /// Accessible name, e.g. "row 3 column 5, 2 adjacent mines".
+    pub label: String,
 }
 
 impl Cell {
@@ -103,6 +183,60 @@ impl Cell {
             kind,
             icon: String::from(icon),
             class: String::from(class),
+            label: format!(
+                "row {} column {}, {}",
+                pos.row + 1,
+                pos.col + 1,
+                kind.describe(state)
+            ),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels() {
+        let pos = Pos { row: 2, col: 4 };
+        let label = |kind, state| Cell::new(pos, kind, state).label;
+        assert_eq!(
+            label(CellKind::new_open(2), GameState::Playing),
+            "row 3 column 5, 2 adjacent mines"
+        );
+        assert_eq!(
+            label(CellKind::new_open(1), GameState::Playing),
+            "row 3 column 5, 1 adjacent mine"
+        );
+        assert_eq!(
+            label(CellKind::new_mine(), GameState::Playing),
+            "row 3 column 5, unopened"
+        );
+        assert_eq!(
+            label(CellKind::new_mine(), GameState::Lose),
+            "row 3 column 5, mine"
+        );
+        assert_eq!(
+            label(CellKind::Closed { mark: Mark::Flag }, GameState::Playing),
+            "row 3 column 5, flagged"
+        );
+    }
+
+    #[test]
+    fn reads_flagged_cells_saved_before_marks() {
+        let old: Vec<CellKind> =
+            serde_json::from_str(r#"[{"Closed":{"flagged":true}},{"Mine":{"flagged":false}}]"#)
+                .unwrap();
+        assert_eq!(
+            old,
+            [CellKind::Closed { mark: Mark::Flag }, CellKind::new_mine()]
+        );
+
+        let kind = CellKind::Mine {
+            mark: Mark::Question,
+        };
+        let json = serde_json::to_string(&kind).unwrap();
+        assert_eq!(serde_json::from_str::<CellKind>(&json).unwrap(), kind);
+    }
+}
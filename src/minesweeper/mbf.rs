@@ -0,0 +1,144 @@
+//! The `.mbf` board format used by Minesweeper Arbiter and friends.
+//!
+//! A layout only, no game state: one byte each for width and height, the
+//! mine count as a big-endian `u16`, then an `x`, `y` byte pair per mine.
+
+use super::{Minesweeper, Pos};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::Display;
+
+#[derive(Debug, PartialEq)]
+pub enum MbfError {
+    TooShort,
+    WrongLength { expected: usize, found: usize },
+    MineOutOfBounds(Pos),
+    DuplicateMine(Pos),
+    NoMines,
+    TooManyMines,
+}
+
+impl Error for MbfError {}
+
+impl Display for MbfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MbfError::TooShort => f.write_str("file is too short to be a board"),
+            MbfError::WrongLength { expected, found } => {
+                write!(f, "expected {} bytes but found {}", expected, found)
+            }
+            MbfError::MineOutOfBounds(pos) => write!(f, "mine at {:?} is off the board", pos),
+            MbfError::DuplicateMine(pos) => write!(f, "mine at {:?} is listed twice", pos),
+            MbfError::NoMines => f.write_str("mines aren't placed until the first click"),
+            MbfError::TooManyMines => f.write_str("too many mines for the format"),
+        }
+    }
+}
+
+impl Minesweeper {
+    /// The mine layout as `.mbf`. Mines are listed row by row.
+    pub fn to_mbf(&self) -> Result<Vec<u8>, MbfError> {
+        let mines = self.replay().mines;
+        if mines.is_empty() {
+            return Err(MbfError::NoMines);
+        }
+        let mine_count = u16::try_from(mines.len()).map_err(|_| MbfError::TooManyMines)?;
+
+        let mut bytes = vec![self.board.width, self.board.height];
+        bytes.extend(mine_count.to_be_bytes());
+        bytes.extend(mines.iter().flat_map(|pos| [pos.col, pos.row]));
+        Ok(bytes)
+    }
+
+    /// An unstarted game on the layout in an `.mbf` file.
+    pub fn from_mbf(bytes: &[u8]) -> Result<Self, MbfError> {
+        let [width, height, count_hi, count_lo, mines @ ..] = bytes else {
+            return Err(MbfError::TooShort);
+        };
+        let mine_count = u16::from_be_bytes([*count_hi, *count_lo]) as usize;
+        if mines.len() != mine_count * 2 {
+            return Err(MbfError::WrongLength {
+                expected: 4 + mine_count * 2,
+                found: bytes.len(),
+            });
+        }
+
+        let mut seen = HashSet::new();
+        let mines = mines
+            .chunks_exact(2)
+            .map(|xy| {
+                let pos = Pos {
+                    row: xy[1],
+                    col: xy[0],
+                };
+                if pos.row >= *height || pos.col >= *width {
+                    Err(MbfError::MineOutOfBounds(pos))
+                } else if !seen.insert(pos) {
+                    Err(MbfError::DuplicateMine(pos))
+                } else {
+                    Ok(pos)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::from_layout(*height, *width, &mines))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::{Action, GameState};
+
+    const BEGINNER: &[u8] = include_bytes!("fixtures/beginner.mbf");
+
+    #[test]
+    fn load_fixture() {
+        let game = Minesweeper::from_mbf(BEGINNER).unwrap();
+        assert_eq!((game.board.height, game.board.width), (9, 9));
+        assert_eq!(game.mine_count, 10);
+        assert_eq!(game.state, GameState::Unstarted);
+        assert!(game.board.get(Pos { row: 0, col: 2 }).unwrap().is_mine());
+        assert!(game.board.get(Pos { row: 8, col: 7 }).unwrap().is_mine());
+        assert!(!game.is_ranked());
+    }
+
+    #[test]
+    fn fixture_round_trip() {
+        let mut game = Minesweeper::from_mbf(BEGINNER).unwrap();
+        assert_eq!(game.to_mbf().unwrap(), BEGINNER);
+
+        // Playing keeps the loaded layout.
+        game.apply(Action::Open(Pos { row: 4, col: 4 }));
+        assert_eq!(game.to_mbf().unwrap(), BEGINNER);
+    }
+
+    #[test]
+    fn unstarted_game_has_no_layout() {
+        let game = Minesweeper::new(9, 9, 10);
+        assert_eq!(game.to_mbf(), Err(MbfError::NoMines));
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        assert_eq!(
+            Minesweeper::from_mbf(&[9, 9, 0]).err(),
+            Some(MbfError::TooShort)
+        );
+        assert_eq!(
+            Minesweeper::from_mbf(&BEGINNER[..BEGINNER.len() - 1]).err(),
+            Some(MbfError::WrongLength {
+                expected: BEGINNER.len(),
+                found: BEGINNER.len() - 1
+            })
+        );
+        assert_eq!(
+            Minesweeper::from_mbf(&[2, 2, 0, 1, 2, 0]).err(),
+            Some(MbfError::MineOutOfBounds(Pos { row: 0, col: 2 }))
+        );
+        assert_eq!(
+            Minesweeper::from_mbf(&[2, 2, 0, 2, 1, 1, 1, 1]).err(),
+            Some(MbfError::DuplicateMine(Pos { row: 1, col: 1 }))
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! The efficiency numbers players compare: 3BV/s, IOE and ZiNi.
+
+use super::{Action, Board, GameState, Minesweeper, Pos, Replay};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Clicks made on the board, including ones that changed nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Clicks {
+    pub left: usize,
+    pub right: usize,
+    pub chord: usize,
+}
+
+impl Clicks {
+    pub fn count(&mut self, action: Action) {
+        match action {
+            Action::Open(_) => self.left += 1,
+            Action::Flag(_) => self.right += 1,
+            Action::Chord(_) => self.chord += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.left + self.right + self.chord
+    }
+
+    /// Whether these are at least the clicks in `other` of every kind, e.g.
+    /// the ones that made it into a replay.
+    pub fn covers(&self, other: &Clicks) -> bool {
+        self.left >= other.left && self.right >= other.right && self.chord >= other.chord
+    }
+}
+
+/// How efficiently a won game was played.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    pub bbbv: usize,
+    pub zini: usize,
+    pub clicks: Clicks,
+    /// Milliseconds from the first click to the last.
+    pub time: u32,
+}
+
+impl Metrics {
+    pub fn new(board: &Board, clicks: Clicks, time: u32) -> Self {
+        Self {
+            bbbv: board.bbbv(),
+            zini: board.zini(),
+            clicks,
+            time,
+        }
+    }
+
+    pub fn bbbv_per_second(&self) -> f64 {
+        match self.time {
+            0 => 0.0,
+            time => self.bbbv as f64 * 1000.0 / time as f64,
+        }
+    }
+
+    /// Index of efficiency: 3BV per click.
+    pub fn ioe(&self) -> f64 {
+        match self.clicks.total() {
+            0 => 0.0,
+            clicks => self.bbbv as f64 / clicks as f64,
+        }
+    }
+
+    /// ZiNi per click, how close the game came to the fewest clicks with
+    /// flagging and chording.
+    pub fn zini_efficiency(&self) -> f64 {
+        match self.clicks.total() {
+            0 => 0.0,
+            clicks => self.zini as f64 / clicks as f64,
+        }
+    }
+}
+
+impl Minesweeper {
+    /// The game's metrics, once it's won.
+    pub fn metrics(&self) -> Option<Metrics> {
+        if self.state != GameState::Win {
+            return None;
+        }
+        let time = self.history.last().map_or(0, |entry| entry.event.time);
+        Some(Metrics::new(&self.board, self.clicks, time))
+    }
+}
+
+impl Replay {
+    /// The clicks recorded in the replay. Clicks that changed nothing aren't
+    /// recorded, so the player may have made more.
+    pub fn clicks(&self) -> Clicks {
+        let mut clicks = Clicks::default();
+        self.events
+            .iter()
+            .for_each(|event| clicks.count(event.action));
+        clicks
+    }
+}
+
+/// What a simulated player has cleared and flagged so far.
+struct Sweep<'a> {
+    board: &'a Board,
+    revealed: HashSet<Pos>,
+    flagged: HashSet<Pos>,
+}
+
+impl<'a> Sweep<'a> {
+    fn is_mine(&self, pos: Pos) -> bool {
+        self.board.get(pos).map_or(false, |kind| kind.is_mine())
+    }
+
+    fn number(&self, pos: Pos) -> usize {
+        self.board
+            .iter_neighbors(pos)
+            .filter(|&p| self.is_mine(p))
+            .count()
+    }
+
+    fn reveal(&mut self, pos: Pos) {
+        let mut stack = vec![pos];
+        while let Some(pos) = stack.pop() {
+            if self.is_mine(pos) || !self.revealed.insert(pos) {
+                continue;
+            }
+            if self.number(pos) == 0 {
+                stack.extend(self.board.iter_neighbors(pos));
+            }
+        }
+    }
+
+    /// The 3BV a click on `pos` still earns: the opening it belongs to, or the
+    /// number itself if no opening reveals it.
+    fn unit(&self, pos: Pos) -> Option<Pos> {
+        if self.is_mine(pos) || self.revealed.contains(&pos) {
+            return None;
+        }
+        if self.number(pos) == 0 {
+            return Some(self.opening_of(pos));
+        }
+        let by_opening = self
+            .board
+            .iter_neighbors(pos)
+            .any(|p| !self.is_mine(p) && self.number(p) == 0);
+        (!by_opening).then_some(pos)
+    }
+
+    /// The first cell of the opening around zero cell `pos`, to tell
+    /// openings apart.
+    fn opening_of(&self, pos: Pos) -> Pos {
+        let mut seen = HashSet::from([pos]);
+        let mut stack = vec![pos];
+        while let Some(pos) = stack.pop() {
+            for p in self.board.iter_neighbors(pos) {
+                if self.number(p) == 0 && !self.is_mine(p) && seen.insert(p) {
+                    stack.push(p);
+                }
+            }
+        }
+        seen.into_iter()
+            .min_by_key(|p| (p.row, p.col))
+            .unwrap_or(pos)
+    }
+
+    /// What chording `pos` is worth: the 3BV it clears, less the clicks to
+    /// open it, flag around it and chord.
+    fn premium(&self, pos: Pos) -> isize {
+        let units: HashSet<_> = self
+            .board
+            .iter_neighbors(pos)
+            .chain([pos])
+            .filter_map(|p| self.unit(p))
+            .collect();
+        let flags = self
+            .board
+            .iter_neighbors(pos)
+            .filter(|&p| self.is_mine(p) && !self.flagged.contains(&p))
+            .count();
+        let open = !self.revealed.contains(&pos) as usize;
+        units.len() as isize - (open + flags + 1) as isize
+    }
+}
+
+impl Board {
+    /// The layout's 3BV: the fewest clicks that clear the board, one per
+    /// opening plus one per number no opening reveals.
+    pub fn bbbv(&self) -> usize {
+        let is_mine = |pos| self.get(pos).map_or(false, |kind| kind.is_mine());
+        let is_zero = |pos| !is_mine(pos) && !self.iter_neighbors(pos).any(is_mine);
+
+        let mut revealed = vec![vec![false; self.width as usize]; self.height as usize];
+        let mut clicks = 0;
+        for start in self.iter_pos().filter(|&pos| is_zero(pos)) {
+            if revealed[start.row as usize][start.col as usize] {
+                continue;
+            }
+            clicks += 1;
+            revealed[start.row as usize][start.col as usize] = true;
+            let mut stack = vec![start];
+            while let Some(pos) = stack.pop() {
+                for neighbor in self.iter_neighbors(pos) {
+                    let seen = &mut revealed[neighbor.row as usize][neighbor.col as usize];
+                    if !*seen {
+                        *seen = true;
+                        if is_zero(neighbor) {
+                            stack.push(neighbor);
+                        }
+                    }
+                }
+            }
+        }
+
+        clicks
+            + self
+                .iter_pos()
+                .filter(|&pos| !is_mine(pos) && !revealed[pos.row as usize][pos.col as usize])
+                .count()
+    }
+
+    /// A greedy estimate of the fewest clicks that clear the board when
+    /// flagging and chording: chord wherever that beats clicking, then click
+    /// whatever is left.
+    pub fn zini(&self) -> usize {
+        let mut sweep = Sweep {
+            board: self,
+            revealed: HashSet::new(),
+            flagged: HashSet::new(),
+        };
+        let numbers: Vec<_> = self
+            .iter_pos()
+            .filter(|&pos| !sweep.is_mine(pos) && sweep.number(pos) > 0)
+            .collect();
+
+        let mut clicks = 0;
+        loop {
+            let best = numbers
+                .iter()
+                .map(|&pos| (sweep.premium(pos), pos))
+                .filter(|&(premium, _)| premium > 0)
+                .reduce(|best, next| if next.0 > best.0 { next } else { best });
+            let Some((_, pos)) = best else {
+                break;
+            };
+
+            if !sweep.revealed.contains(&pos) {
+                clicks += 1;
+                sweep.reveal(pos);
+            }
+            let mines: Vec<_> = self
+                .iter_neighbors(pos)
+                .filter(|&p| sweep.is_mine(p))
+                .collect();
+            for mine in mines {
+                clicks += sweep.flagged.insert(mine) as usize;
+            }
+            clicks += 1;
+            self.iter_neighbors(pos).for_each(|p| sweep.reveal(p));
+        }
+
+        let left: HashSet<_> = self.iter_pos().filter_map(|pos| sweep.unit(pos)).collect();
+        clicks + left.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bbbv_counts_openings_and_lone_numbers() {
+        // One opening in the bottom left reveals everything but the two
+        // numbers on the right edge, which each need their own click.
+        let b = Board::from_matrix(vec![vec![0, 0, 1, 0], vec![0, 0, 0, 0], vec![0, 0, 0, 1]]);
+        assert_eq!(b.bbbv(), 1 + 2);
+
+        let all_numbers = Board::from_matrix(vec![vec![0, 1, 0]]);
+        assert_eq!(all_numbers.bbbv(), 2);
+
+        let one_opening = Board::from_matrix(vec![vec![0, 0], vec![0, 0]]);
+        assert_eq!(one_opening.bbbv(), 1);
+    }
+
+    #[test]
+    fn zini_chords_through_numbers() {
+        // Every cell is a 1, so clicking takes 8 and chording around the
+        // mine takes fewer.
+        let board = Board::from_matrix(vec![vec![0, 0, 0], vec![0, 1, 0], vec![0, 0, 0]]);
+        assert_eq!(board.bbbv(), 8);
+        assert_eq!(board.zini(), 5);
+    }
+
+    #[test]
+    fn zini_never_beats_one_click_openings() {
+        let board = Board::from_matrix(vec![vec![0, 0, 0], vec![0, 0, 0], vec![0, 0, 1]]);
+        assert_eq!(board.bbbv(), 1);
+        assert_eq!(board.zini(), 1);
+    }
+
+    #[test]
+    fn metrics_of_a_won_game() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0]]);
+        game.apply_at(Action::Flag(Pos { row: 0, col: 0 }), 0.0);
+        game.apply_at(Action::Open(Pos { row: 0, col: 0 }), 500.0);
+        assert!(game.metrics().is_none());
+        game.apply_at(Action::Open(Pos { row: 0, col: 2 }), 2000.0);
+
+        let metrics = game.metrics().unwrap();
+        assert_eq!(
+            metrics.clicks,
+            Clicks {
+                left: 2,
+                right: 1,
+                chord: 0
+            }
+        );
+        assert_eq!(metrics.bbbv, 1);
+        assert_eq!(metrics.time, 2000);
+        assert_eq!(metrics.bbbv_per_second(), 0.5);
+        assert_eq!(metrics.ioe(), 1.0 / 3.0);
+
+        // The click on the flag changed nothing, so the replay lacks it.
+        let replay = game.replay();
+        assert_eq!(replay.clicks().total(), 2);
+        assert!(metrics.clicks.covers(&replay.clicks()));
+    }
+}
@@ -1,18 +1,35 @@
 mod board;
 mod cell;
+mod mbf;
+mod metrics;
+mod player_view;
 mod pos;
-
-use rand::Rng;
+mod probability;
+mod raw;
+mod replay;
+mod snapshot;
+pub mod solver;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::fmt::Display;
 
-use board::Board;
+pub use board::Board;
 pub use cell::Cell;
 pub use cell::CellKind;
+pub use cell::Mark;
+pub use mbf::MbfError;
+pub use metrics::{Clicks, Metrics};
+pub use player_view::PlayerView;
 pub use pos::Pos;
+pub use probability::Probabilities;
+pub use raw::RawError;
+pub use replay::{Replay, ReplayError, ReplayEvent};
+pub use snapshot::SnapshotError;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum GameState {
     Unstarted,
     Playing,
@@ -45,12 +62,101 @@ impl std::fmt::Display for Difficulty {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// What the first click is protected from when the mines are placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum FirstClickPolicy {
+    /// Mines go anywhere, so the first click can lose.
+    None,
+    /// The clicked cell is never a mine.
+    #[default]
+    SafeCell,
+    /// The clicked cell and its neighbors are never mines, so the first click
+    /// always opens an area.
+    SafeOpening,
+    /// Like [`FirstClickPolicy::SafeOpening`], and the whole board can be
+    /// cleared from there without guessing.
+    NoGuess,
+}
+
+impl FirstClickPolicy {
+    pub const ALL: [FirstClickPolicy; 4] = [
+        FirstClickPolicy::None,
+        FirstClickPolicy::SafeCell,
+        FirstClickPolicy::SafeOpening,
+        FirstClickPolicy::NoGuess,
+    ];
+
+    pub fn id(&self) -> u8 {
+        match self {
+            FirstClickPolicy::None => 0,
+            FirstClickPolicy::SafeCell => 1,
+            FirstClickPolicy::SafeOpening => 2,
+            FirstClickPolicy::NoGuess => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|policy| policy.id() == id)
+    }
+}
+
+impl std::fmt::Display for FirstClickPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FirstClickPolicy::None => write!(f, "Unprotected"),
+            FirstClickPolicy::SafeCell => write!(f, "Safe cell"),
+            FirstClickPolicy::SafeOpening => write!(f, "Safe opening"),
+            FirstClickPolicy::NoGuess => write!(f, "No guessing"),
+        }
+    }
+}
+
+/// A cell suggested by [`Minesweeper::hint`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hint {
+    pub pos: Pos,
+    /// Chance the cell is a mine; 0 when it's provably safe.
+    pub mine_chance: f64,
+    /// False when `mine_chance` is an estimate, so the cell may not really be
+    /// the safest.
+    pub exact: bool,
+}
+
+impl Hint {
+    /// A cell known to be safe.
+    pub fn safe(pos: Pos) -> Self {
+        Hint {
+            pos,
+            mine_chance: 0.0,
+            exact: true,
+        }
+    }
+}
+
+impl std::fmt::Display for Hint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.mine_chance, self.exact) {
+            (p, _) if p == 0.0 => write!(f, "Safe to open"),
+            (p, true) => write!(f, "No safe cell; {:.0}% chance of a mine", p * 100.0),
+            (p, false) => write!(
+                f,
+                "No safe cell; about {:.0}% chance of a mine (estimated)",
+                p * 100.0
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Setting {
     pub difficulty: Difficulty,
     pub width: u8,
     pub height: u8,
     pub mine_count: usize,
+    #[serde(default)]
+    pub first_click: FirstClickPolicy,
+    #[serde(default)]
+    pub question_marks: bool,
 }
 
 pub const SETTINGS: [Setting; 3] = [
@@ -59,18 +165,24 @@ pub const SETTINGS: [Setting; 3] = [
         width: 9,
         height: 9,
         mine_count: 10,
+        first_click: FirstClickPolicy::SafeCell,
+        question_marks: false,
     },
     Setting {
         difficulty: Difficulty::Intermediate,
         width: 16,
         height: 16,
         mine_count: 40,
+        first_click: FirstClickPolicy::SafeCell,
+        question_marks: false,
     },
     Setting {
         difficulty: Difficulty::Expert,
         width: 30,
         height: 16,
         mine_count: 99,
+        first_click: FirstClickPolicy::SafeCell,
+        question_marks: false,
     },
 ];
 
@@ -79,8 +191,15 @@ pub const CUSTOM: Setting = Setting {
     width: 9,
     height: 9,
     mine_count: 10,
+    first_click: FirstClickPolicy::SafeCell,
+    question_marks: false,
 };
 
+/// Number of layouts tried before a no-guess game gives up and is dealt a
+/// safe opening instead. A count rather than a time limit, so the same seed
+/// always ends up with the same board.
+const NO_GUESS_ATTEMPTS: usize = 1000;
+
 #[derive(Debug)]
 pub enum MinesweeperError {
     GameError,
@@ -96,23 +215,96 @@ impl Display for MinesweeperError {
     }
 }
 
+/// A player move on the board.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    Open(Pos),
+    Flag(Pos),
+    Chord(Pos),
+}
+
+/// An applied action along with the board it was applied to.
+#[derive(Clone)]
+struct HistoryEntry {
+    event: ReplayEvent,
+    board: Board,
+    state: GameState,
+}
+
+#[derive(Clone)]
 pub struct Minesweeper {
     pub mine_count: usize,
     pub board: Board,
     pub state: GameState,
+    /// Seed for mine placement. The same seed and first click always produce
+    /// the same layout.
+    pub seed: u64,
+    /// How the board was dealt, which [`Minesweeper::create_mines`] can turn
+    /// from no-guess to a safe opening.
+    pub first_click: FirstClickPolicy,
+    /// Whether flagging a flag question-marks it instead of clearing it.
+    pub question_marks: bool,
+    pub hints_used: usize,
+    pub undos_used: usize,
+    pub clicks: Clicks,
+    /// Loaded from outside the game (a snapshot or a board file), so the
+    /// layout isn't the seed's or how it was played is unknown.
+    pub restored: bool,
+    history: Vec<HistoryEntry>,
+    redo: Vec<ReplayEvent>,
+    /// Timestamp of the first recorded action.
+    started_at: Option<f64>,
 }
 
 impl Minesweeper {
     pub fn new(height: u8, width: u8, mine_count: usize) -> Self {
+        Self::with_seed(height, width, mine_count, rand::random())
+    }
+
+    pub fn with_seed(height: u8, width: u8, mine_count: usize, seed: u64) -> Self {
         Minesweeper {
             mine_count,
             board: Board::new(height, width),
             state: GameState::Unstarted,
+            seed,
+            first_click: FirstClickPolicy::SafeCell,
+            question_marks: false,
+            hints_used: 0,
+            undos_used: 0,
+            clicks: Clicks::default(),
+            restored: false,
+            history: Vec::new(),
+            redo: Vec::new(),
+            started_at: None,
         }
     }
 
     pub fn from_setting(setting: Setting) -> Self {
-        Self::new(setting.height, setting.width, setting.mine_count)
+        Self::from_setting_with_seed(setting, rand::random())
+    }
+
+    pub fn from_setting_with_seed(setting: Setting, seed: u64) -> Self {
+        Self {
+            first_click: setting.first_click,
+            question_marks: setting.question_marks,
+            ..Self::with_seed(setting.height, setting.width, setting.mine_count, seed)
+        }
+    }
+
+    /// A game whose mines are placed before the first click, so everyone
+    /// dealt `seed` plays the same board wherever they start. The area
+    /// around `start` is kept clear to give them a safe place to begin.
+    pub fn dealt(setting: Setting, seed: u64, start: Pos) -> Self {
+        let mut game = Self::from_setting_with_seed(
+            Setting {
+                first_click: FirstClickPolicy::SafeOpening,
+                ..setting
+            },
+            seed,
+        );
+        game.create_mines(Some(start));
+        game.state = GameState::Unstarted;
+        game
     }
 
     pub fn from_matrix(matrix: Vec<Vec<i32>>) -> Self {
@@ -123,33 +315,230 @@ impl Minesweeper {
             .filter(|&kind| kind.is_mine())
             .count();
         Self {
-            mine_count,
             board,
             state: GameState::Playing,
+            ..Self::with_seed(0, 0, mine_count, 0)
+        }
+    }
+
+    /// A game on a fixed layout, e.g. one loaded from a board file. The first
+    /// click gets no protection.
+    pub fn from_layout(height: u8, width: u8, mines: &[Pos]) -> Self {
+        let mut game = Self::with_seed(height, width, mines.len(), 0);
+        for &pos in mines {
+            _ = game.board.set(pos, CellKind::new_mine());
         }
+        game.restored = true;
+        game
     }
 
+    /// Places the mines for a first click at `start_pos`, unless the board
+    /// already has them. A no-guess game that can't find a solvable layout
+    /// is dealt as [`FirstClickPolicy::SafeOpening`], and its `first_click`
+    /// says so.
     pub fn create_mines(&mut self, start_pos: Option<Pos>) {
+        let placed = self.board.iter().flatten().filter(|kind| kind.is_mine());
+        if self.mine_count > 0 && placed.count() == self.mine_count {
+            self.state = GameState::Playing;
+            return;
+        }
+
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        match (start_pos, self.first_click) {
+            (Some(pos), FirstClickPolicy::NoGuess) => {
+                if !self.place_no_guess_mines(&mut rng, pos) {
+                    // Dealt the way a safe opening game with this seed is,
+                    // so replays of it still check out.
+                    self.first_click = FirstClickPolicy::SafeOpening;
+                    self.board = Board::new(self.board.height, self.board.width);
+                    let excluded = self.opening(pos);
+                    let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+                    self.place_mines(&mut rng, &excluded)
+                }
+            }
+            (Some(pos), FirstClickPolicy::SafeOpening) => {
+                let excluded = self.opening(pos);
+                self.place_mines(&mut rng, &excluded)
+            }
+            (Some(pos), FirstClickPolicy::SafeCell) => self.place_mines(&mut rng, &[pos]),
+            _ => self.place_mines(&mut rng, &[]),
+        }
+
+        self.state = GameState::Playing;
+    }
+
+    /// The cells kept free of mines so a first click at `start_pos` opens an
+    /// area: the start cell and its neighbors, or just the start cell when
+    /// the board has no room for the mines otherwise.
+    fn opening(&self, start_pos: Pos) -> Vec<Pos> {
+        let mut excluded: Vec<_> = self.board.iter_neighbors(start_pos).collect();
+        excluded.push(start_pos);
+        if self.board.iter_pos().count() - excluded.len() < self.mine_count {
+            excluded = vec![start_pos];
+        }
+        excluded
+    }
+
+    /// Keeps drawing layouts around an opening at `start_pos` until the
+    /// solver can clear the board from there. Returns whether one was found
+    /// within [`NO_GUESS_ATTEMPTS`].
+    fn place_no_guess_mines(&mut self, rng: &mut ChaCha8Rng, start_pos: Pos) -> bool {
+        let blank = self.board.clone();
+        let excluded = self.opening(start_pos);
+
+        for _ in 0..NO_GUESS_ATTEMPTS {
+            self.board = blank.clone();
+            self.place_mines(rng, &excluded);
+            if self.is_solvable_from(start_pos) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn place_mines(&mut self, rng: &mut ChaCha8Rng, excluded: &[Pos]) {
         let mut allowable_mine_pos: Vec<_> = self
             .board
             .iter_pos()
-            .filter(|&pos| Some(pos) != start_pos)
+            .filter(|pos| !excluded.contains(pos))
             .collect();
 
         let mut mines_created = 0;
         while mines_created < self.mine_count {
-            let index = rand::thread_rng().gen_range(0..allowable_mine_pos.len());
+            let index = rng.gen_range(0..allowable_mine_pos.len());
             let mine_pos = allowable_mine_pos.remove(index);
             if self
                 .board
-                .set(mine_pos, CellKind::Mine { flagged: false })
+                .set(mine_pos, CellKind::Mine { mark: Mark::None })
                 .is_ok()
             {
                 mines_created += 1;
             }
         }
+    }
 
-        self.state = GameState::Playing;
+    /// Plays a copy of the game from `start_pos` using only solver deductions.
+    fn is_solvable_from(&self, start_pos: Pos) -> bool {
+        let mut game = self.clone();
+        game.state = GameState::Playing;
+        game.open_cell(start_pos);
+        while game.state == GameState::Playing {
+            let safe = solver::solve_with_mine_count(&game.board, game.mine_count).safe;
+            if safe.is_empty() {
+                return false;
+            }
+            safe.into_iter().for_each(|pos| game.open_cell(pos));
+        }
+        game.state == GameState::Win
+    }
+
+    /// Applies `action` without a clock; see [`Minesweeper::apply_at`].
+    pub fn apply(&mut self, action: Action) {
+        self.apply_at(action, self.started_at.unwrap_or_default());
+    }
+
+    /// Applies `action` at `now`, a timestamp in milliseconds from a monotonic
+    /// clock, and records it for [`Minesweeper::undo`] and the replay. Actions
+    /// that don't change the game are counted as clicks but not recorded.
+    pub fn apply_at(&mut self, action: Action, now: f64) {
+        if !self.state.is_over() {
+            self.clicks.count(action);
+        }
+        let time = self.started_at.map_or(0.0, |start| now - start).max(0.0) as u32;
+        if self.record(ReplayEvent { time, action }) {
+            self.started_at.get_or_insert(now);
+            self.redo.clear();
+        }
+    }
+
+    /// Milliseconds since the first recorded action, if there is one.
+    pub fn elapsed(&self, now: f64) -> Option<f64> {
+        self.started_at.map(|start| now - start)
+    }
+
+    /// Moves the clock so the first recorded action happened `elapsed`
+    /// milliseconds before `now`, e.g. for a game restored on another page.
+    pub fn resume_clock(&mut self, now: f64, elapsed: f64) {
+        if !self.history.is_empty() {
+            self.started_at = Some(now - elapsed);
+        }
+    }
+
+    fn record(&mut self, event: ReplayEvent) -> bool {
+        let board = self.board.clone();
+        let state = self.state;
+        match event.action {
+            Action::Open(pos) => self.open_cell(pos),
+            Action::Flag(pos) => self.flag_cell(pos),
+            Action::Chord(pos) => self.chorded_open(pos),
+        }
+
+        let changed = self.board != board || self.state != state;
+        if changed {
+            self.history.push(HistoryEntry {
+                event,
+                board,
+                state,
+            });
+        }
+        changed
+    }
+
+    /// Reverts the last applied action, including the one that lost the game.
+    /// A game that has been undone is practice and no longer ranked.
+    pub fn undo(&mut self) -> bool {
+        let Some(entry) = self.history.pop() else {
+            return false;
+        };
+        self.board = entry.board;
+        self.state = entry.state;
+        self.redo.push(entry.event);
+        self.undos_used += 1;
+        if self.history.is_empty() {
+            self.started_at = None;
+        }
+        true
+    }
+
+    /// Re-applies the last undone action without a clock; see
+    /// [`Minesweeper::redo_at`].
+    pub fn redo(&mut self) -> bool {
+        self.redo_at(self.started_at.unwrap_or_default())
+    }
+
+    /// Re-applies the last undone action at `now`, counting it as a click the
+    /// way [`Minesweeper::apply_at`] does. Redoing the first action starts the
+    /// clock again from `now`.
+    pub fn redo_at(&mut self, now: f64) -> bool {
+        let Some(event) = self.redo.pop() else {
+            return false;
+        };
+        if !self.state.is_over() {
+            self.clicks.count(event.action);
+        }
+        if self.record(event) {
+            self.started_at.get_or_insert(now);
+        }
+        true
+    }
+
+    /// Everything needed to play this game back: the mine layout and every
+    /// recorded action that wasn't undone.
+    pub fn replay(&self) -> Replay {
+        Replay {
+            height: self.board.height,
+            width: self.board.width,
+            mine_count: self.mine_count,
+            seed: self.seed,
+            first_click: self.first_click,
+            question_marks: self.question_marks,
+            mines: self
+                .board
+                .iter_pos()
+                .filter(|&pos| self.board.get(pos).map_or(false, |kind| kind.is_mine()))
+                .collect(),
+            events: self.history.iter().map(|entry| entry.event).collect(),
+        }
     }
 
     pub fn flag_cell(&mut self, pos: Pos) {
@@ -157,12 +546,15 @@ impl Minesweeper {
             return;
         }
 
+        let question_marks = self.question_marks;
         match self.board.get(pos) {
-            Some(CellKind::Closed { flagged }) => {
-                _ = self.board.set(pos, CellKind::Closed { flagged: !flagged })
+            Some(CellKind::Closed { mark }) => {
+                let mark = mark.next(question_marks);
+                _ = self.board.set(pos, CellKind::Closed { mark })
             }
-            Some(CellKind::Mine { flagged }) => {
-                _ = self.board.set(pos, CellKind::Mine { flagged: !flagged })
+            Some(CellKind::Mine { mark }) => {
+                let mark = mark.next(question_marks);
+                _ = self.board.set(pos, CellKind::Mine { mark })
             }
             _ => {}
         }
@@ -180,7 +572,7 @@ impl Minesweeper {
                 return;
             }
             match kind {
-                CellKind::Mine { flagged: false } => self.state = GameState::Lose,
+                CellKind::Mine { .. } => self.state = GameState::Lose,
                 CellKind::Closed { .. } => self.check_neighbor(pos),
                 _ => (),
             }
@@ -217,7 +609,7 @@ impl Minesweeper {
         let pos_kind = self
             .board
             .get(pos)
-            .unwrap_or(&CellKind::Closed { flagged: true });
+            .unwrap_or(&CellKind::Closed { mark: Mark::Flag });
         if !CellKind::is_open(pos_kind) {
             return;
         }
@@ -241,6 +633,7 @@ impl Minesweeper {
         }
     }
 
+    /// Won once only mines are left closed, however they're marked.
     fn check_win_condition(&mut self) {
         let closed_cells = self.board.iter().flatten().filter(|kind| kind.is_closed());
         if closed_cells.count() == 0 {
@@ -260,10 +653,60 @@ impl Minesweeper {
         let kind = self
             .board
             .get(pos)
-            .unwrap_or(&CellKind::Closed { flagged: false });
+            .unwrap_or(&CellKind::Closed { mark: Mark::None });
         Cell::new(pos, *kind, self.state)
     }
 
+    /// Exact chance that each closed cell is a mine, given the open numbers
+    /// and `mine_count`, or an estimate marked as such when the frontier is
+    /// too tangled to count out quickly. Open cells are left out.
+    pub fn mine_probabilities(&self) -> Probabilities {
+        probability::mine_probabilities(&self.board, self.mine_count)
+    }
+
+    /// Suggests a cell to open: a provably safe one if there is any, otherwise
+    /// the one least likely to be a mine, which is only a best guess when the
+    /// chances are estimated. Every hint given is counted.
+    pub fn hint(&mut self) -> Option<Hint> {
+        if self.state != GameState::Playing {
+            return None;
+        }
+
+        let is_unflagged = |pos: &Pos| self.board.get(*pos).map_or(false, |k| !k.is_flagged());
+        let hint = solver::solve_with_mine_count(&self.board, self.mine_count)
+            .safe
+            .into_iter()
+            .filter(is_unflagged)
+            .min_by_key(|pos| (pos.row, pos.col))
+            .map(Hint::safe)
+            .or_else(|| {
+                let probabilities = self.mine_probabilities();
+                probabilities
+                    .cells
+                    .into_iter()
+                    .filter(|(pos, _)| is_unflagged(pos))
+                    .min_by(|(a, p), (b, q)| {
+                        p.total_cmp(q).then((a.row, a.col).cmp(&(b.row, b.col)))
+                    })
+                    .map(|(pos, mine_chance)| Hint {
+                        pos,
+                        mine_chance,
+                        exact: probabilities.exact,
+                    })
+            });
+
+        if hint.is_some() {
+            self.hints_used += 1;
+        }
+        hint
+    }
+
+    /// Whether the game was played unaided from a dealt board: no hints, no
+    /// undo and not loaded from outside. Only such games count in the stats.
+    pub fn is_ranked(&self) -> bool {
+        self.hints_used == 0 && self.undos_used == 0 && !self.restored
+    }
+
     pub fn chorded_cells(&self, pos: Pos) -> Vec<Pos> {
         let mut cells = self
             .board
@@ -368,6 +811,288 @@ mod tests {
         assert_eq!(game.state, GameState::Playing);
     }
 
+    #[test]
+    fn same_seed_same_layout() {
+        let pos = Pos { row: 4, col: 4 };
+        let mut first = Minesweeper::from_setting_with_seed(SETTINGS[1], 42);
+        let mut second = Minesweeper::from_setting_with_seed(SETTINGS[1], 42);
+        first.create_mines(Some(pos));
+        second.create_mines(Some(pos));
+        assert!(first
+            .board
+            .iter_pos()
+            .all(|p| first.board.get(p) == second.board.get(p)));
+    }
+
+    #[test]
+    fn different_seed_different_layout() {
+        let pos = Pos { row: 4, col: 4 };
+        let mut first = Minesweeper::from_setting_with_seed(SETTINGS[1], 1);
+        let mut second = Minesweeper::from_setting_with_seed(SETTINGS[1], 2);
+        first.create_mines(Some(pos));
+        second.create_mines(Some(pos));
+        assert!(first
+            .board
+            .iter_pos()
+            .any(|p| first.board.get(p) != second.board.get(p)));
+    }
+
+    #[test]
+    fn no_guess_boards_are_solvable() {
+        let pos = Pos { row: 4, col: 4 };
+        for setting in &SETTINGS[..2] {
+            let setting = Setting {
+                first_click: FirstClickPolicy::NoGuess,
+                ..*setting
+            };
+            for seed in 0..20 {
+                let mut game = Minesweeper::from_setting_with_seed(setting, seed);
+                game.create_mines(Some(pos));
+                assert_eq!(count_mines(&game), game.mine_count);
+                assert!(game.is_solvable_from(pos), "seed {}\n{}", seed, game);
+            }
+        }
+    }
+
+    #[test]
+    fn no_guess_expert_boards_are_solvable() {
+        let setting = Setting {
+            first_click: FirstClickPolicy::NoGuess,
+            ..SETTINGS[2]
+        };
+        let pos = Pos { row: 8, col: 15 };
+        for seed in 0..5 {
+            let mut game = Minesweeper::from_setting_with_seed(setting, seed);
+            game.create_mines(Some(pos));
+            assert_eq!(game.first_click, FirstClickPolicy::NoGuess);
+            assert_eq!(count_mines(&game), game.mine_count);
+            assert!(game.is_solvable_from(pos), "seed {}\n{}", seed, game);
+        }
+    }
+
+    #[test]
+    fn no_guess_falls_back_to_safe_opening() {
+        // The first click can only say the mine is one of the other three.
+        let setting = Setting {
+            difficulty: Difficulty::Custom,
+            height: 2,
+            width: 2,
+            mine_count: 1,
+            first_click: FirstClickPolicy::NoGuess,
+            question_marks: false,
+        };
+        let pos = Pos { row: 0, col: 0 };
+        let mut game = Minesweeper::from_setting_with_seed(setting, 3);
+        game.create_mines(Some(pos));
+        assert_eq!(game.first_click, FirstClickPolicy::SafeOpening);
+
+        let mut safe_opening = Minesweeper::from_setting_with_seed(
+            Setting {
+                first_click: FirstClickPolicy::SafeOpening,
+                ..setting
+            },
+            3,
+        );
+        safe_opening.create_mines(Some(pos));
+        assert!(game.board == safe_opening.board);
+    }
+
+    #[test]
+    fn no_guess_is_reproducible() {
+        let setting = Setting {
+            first_click: FirstClickPolicy::NoGuess,
+            ..SETTINGS[0]
+        };
+        let pos = Pos { row: 0, col: 0 };
+        let mut first = Minesweeper::from_setting_with_seed(setting, 7);
+        let mut second = Minesweeper::from_setting_with_seed(setting, 7);
+        first.create_mines(Some(pos));
+        second.create_mines(Some(pos));
+        assert!(first
+            .board
+            .iter_pos()
+            .all(|p| first.board.get(p) == second.board.get(p)));
+    }
+
+    #[test]
+    fn first_click_policies() {
+        let pos = Pos { row: 0, col: 0 };
+        let opens = |first_click, seed| {
+            let setting = Setting {
+                first_click,
+                ..SETTINGS[0]
+            };
+            let mut game = Minesweeper::from_setting_with_seed(setting, seed);
+            game.open_cell(pos);
+            game.board.get(pos).copied()
+        };
+
+        for seed in 0..20 {
+            assert!(opens(FirstClickPolicy::SafeCell, seed).unwrap().is_open());
+            assert_eq!(
+                opens(FirstClickPolicy::SafeOpening, seed),
+                Some(CellKind::new_open(0))
+            );
+        }
+
+        let mut dense = Minesweeper::with_seed(3, 3, 8, 0);
+        dense.first_click = FirstClickPolicy::None;
+        let lost = (0..20).any(|seed| {
+            let mut game = Minesweeper {
+                seed,
+                ..dense.clone()
+            };
+            game.open_cell(pos);
+            game.state == GameState::Lose
+        });
+        assert!(lost);
+
+        dense.first_click = FirstClickPolicy::SafeOpening;
+        dense.open_cell(pos);
+        assert_eq!(dense.state, GameState::Win);
+    }
+
+    #[test]
+    fn dealt_games_share_a_board() {
+        let start = Pos { row: 8, col: 8 };
+        let mut first = Minesweeper::dealt(SETTINGS[1], 11, start);
+        let mut second = Minesweeper::dealt(SETTINGS[1], 11, start);
+        assert_eq!(first.state, GameState::Unstarted);
+
+        // Starting in different places doesn't move the mines.
+        first.open_cell(start);
+        second.open_cell(Pos { row: 0, col: 0 });
+        assert!(first.board.get(start).unwrap().is_open());
+        assert!(first.board.iter_pos().all(
+            |p| first.board.get(p).unwrap().is_mine() == second.board.get(p).unwrap().is_mine()
+        ));
+    }
+
+    #[test]
+    fn hint_prefers_safe_cell() {
+        let mut game = Minesweeper::from_matrix(vec![vec![0, 0, 0], vec![0, 0, 0], vec![1, 0, 1]]);
+        game.open_cell(Pos { row: 0, col: 1 });
+
+        assert!(game.is_ranked());
+        assert_eq!(game.hint(), Some(Hint::safe(Pos { row: 2, col: 1 })));
+        assert_eq!(game.hints_used, 1);
+        assert!(!game.is_ranked());
+    }
+
+    #[test]
+    fn hint_falls_back_to_lowest_risk() {
+        let mut game = Minesweeper::from_matrix(vec![
+            vec![0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![0, 1, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 1],
+        ]);
+        _ = game
+            .board
+            .set(Pos { row: 1, col: 1 }, CellKind::new_open(1));
+
+        let hint = game.hint().unwrap();
+        let probabilities = game.mine_probabilities();
+        assert!(hint.exact);
+        assert_eq!(hint.mine_chance, probabilities.cells[&hint.pos]);
+        assert!(probabilities.cells.values().all(|&p| hint.mine_chance <= p));
+        assert!(!game
+            .board
+            .iter_neighbors(Pos { row: 1, col: 1 })
+            .any(|p| p == hint.pos));
+    }
+
+    #[test]
+    fn hint_when_not_playing() {
+        let mut game = Minesweeper::new(9, 9, 10);
+        assert_eq!(game.hint(), None);
+        assert_eq!(game.hints_used, 0);
+    }
+
+    #[test]
+    fn undo_redo_flag() {
+        let mut game = Minesweeper::new(4, 3, 3);
+        let pos = Pos { row: 0, col: 0 };
+        game.apply(Action::Flag(pos));
+        assert!(game.board.get(pos).unwrap().is_flagged());
+
+        assert!(game.undo());
+        assert!(!game.board.get(pos).unwrap().is_flagged());
+        assert!(!game.undo());
+
+        assert!(game.redo());
+        assert!(game.board.get(pos).unwrap().is_flagged());
+        assert!(!game.redo());
+        assert!(!game.is_ranked());
+    }
+
+    #[test]
+    fn undo_death() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0], vec![0, 0, 1]]);
+        game.apply(Action::Open(Pos { row: 0, col: 1 }));
+        game.apply(Action::Open(Pos { row: 0, col: 0 }));
+        assert_eq!(game.state, GameState::Lose);
+
+        assert!(game.undo());
+        assert_eq!(game.state, GameState::Playing);
+        assert!(game.board.get(Pos { row: 0, col: 1 }).unwrap().is_open());
+    }
+
+    #[test]
+    fn undo_first_click_and_redo_same_layout() {
+        let mut game = Minesweeper::from_setting_with_seed(SETTINGS[0], 3);
+        game.apply(Action::Open(Pos { row: 4, col: 4 }));
+        let board = game.board.clone();
+
+        assert!(game.undo());
+        assert_eq!(game.state, GameState::Unstarted);
+        assert_eq!(count_mines(&game), 0);
+        assert!(game.redo());
+        assert!(game.board == board);
+    }
+
+    #[test]
+    fn redo_after_undoing_everything() {
+        let mut game = Minesweeper::from_setting_with_seed(SETTINGS[0], 3);
+        game.apply_at(Action::Open(Pos { row: 4, col: 4 }), 1000.0);
+        assert!(game.undo());
+        assert_eq!(game.elapsed(2000.0), None);
+
+        assert!(game.redo_at(3000.0));
+        assert_eq!(game.elapsed(3500.0), Some(500.0));
+        assert_eq!(game.clicks.left, 2);
+
+        let closed = game
+            .board
+            .iter_pos()
+            .find(|&pos| !game.board.get(pos).unwrap().is_open())
+            .unwrap();
+        game.apply_at(Action::Flag(closed), 4000.0);
+        let replay = game.replay();
+        assert_eq!(replay.duration(), 1000);
+        assert!(game.clicks.covers(&replay.clicks()));
+    }
+
+    #[test]
+    fn new_action_clears_redo() {
+        let mut game = Minesweeper::new(4, 3, 3);
+        game.apply(Action::Flag(Pos { row: 0, col: 0 }));
+        game.undo();
+        game.apply(Action::Flag(Pos { row: 1, col: 1 }));
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn unchanged_action_not_recorded() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0]]);
+        game.apply(Action::Open(Pos { row: 0, col: 1 }));
+        game.apply(Action::Open(Pos { row: 0, col: 1 }));
+        game.apply(Action::Flag(Pos { row: 0, col: 1 }));
+        assert!(game.undo());
+        assert!(!game.undo());
+    }
+
     #[test]
     fn flagging_cell_closed() {
         let mut game = Minesweeper::new(4, 3, 3);
@@ -400,11 +1125,39 @@ mod tests {
         assert!(game.board.get(pos).unwrap().is_closed());
     }
 
+    #[test]
+    fn question_marks_cycle() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0], vec![0, 0, 0]]);
+        let pos = Pos { row: 0, col: 1 };
+        let mark = |game: &Minesweeper| game.board.get(pos).unwrap().mark();
+
+        game.flag_cell(pos);
+        game.flag_cell(pos);
+        assert_eq!(mark(&game), Mark::None);
+
+        game.question_marks = true;
+        game.flag_cell(pos);
+        game.flag_cell(pos);
+        assert_eq!(mark(&game), Mark::Question);
+
+        // A question mark is no flag: chording around the mine's number
+        // doesn't count it, and the cell still opens.
+        game.open_cell(Pos { row: 1, col: 0 });
+        game.chorded_open(Pos { row: 1, col: 0 });
+        assert_eq!(mark(&game), Mark::Question);
+        game.open_cell(pos);
+        assert!(game.board.get(pos).unwrap().is_open());
+        game.flag_cell(Pos { row: 0, col: 0 });
+        game.flag_cell(Pos { row: 0, col: 0 });
+        game.open_cell(Pos { row: 1, col: 2 });
+        assert_eq!(game.state, GameState::Win);
+    }
+
     #[test]
     fn flag_cell_mine() {
         let mut game = Minesweeper::new(4, 3, 3);
         let pos = Pos { row: 0, col: 0 };
-        _ = game.board.set(pos, CellKind::Mine { flagged: false });
+        _ = game.board.set(pos, CellKind::Mine { mark: Mark::None });
 
         assert!(game.board.get(pos).unwrap().is_mine());
         game.flag_cell(pos);
@@ -417,7 +1170,7 @@ mod tests {
     fn open_cell_with_mine_playing() {
         let mut game = Minesweeper::new(4, 3, 3);
         let pos = Pos { row: 0, col: 0 };
-        _ = game.board.set(pos, CellKind::Mine { flagged: false });
+        _ = game.board.set(pos, CellKind::Mine { mark: Mark::None });
         game.open_cell(pos);
         assert_eq!(game.state, GameState::Lose);
     }
@@ -0,0 +1,101 @@
+use super::{Board, CellKind, FirstClickPolicy, GameState, Minesweeper};
+use serde::{Deserialize, Serialize};
+
+/// The part of a game the player is allowed to see. Mines look like closed
+/// cells until the game is over.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub height: u8,
+    pub width: u8,
+    pub mine_count: usize,
+    pub state: GameState,
+    pub cells: Vec<CellKind>,
+    #[serde(default)]
+    pub question_marks: bool,
+    /// What the board was dealt with, which a no-guess game that couldn't
+    /// find its board falls back from.
+    #[serde(default)]
+    pub first_click: FirstClickPolicy,
+}
+
+impl Minesweeper {
+    pub fn player_view(&self) -> PlayerView {
+        let cells = self
+            .board
+            .iter()
+            .flatten()
+            .map(|&kind| match kind {
+                CellKind::Mine { mark } if !self.state.is_over() => CellKind::Closed { mark },
+                kind => kind,
+            })
+            .collect();
+        PlayerView {
+            height: self.board.height,
+            width: self.board.width,
+            mine_count: self.mine_count,
+            state: self.state,
+            cells,
+            question_marks: self.question_marks,
+            first_click: self.first_click,
+        }
+    }
+
+    /// A game that can only be rendered, built from what the player saw.
+    pub fn from_player_view(view: PlayerView) -> Self {
+        let mut board = Board::new(view.height, view.width);
+        board
+            .iter_pos()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .zip(view.cells)
+            .for_each(|(pos, kind)| _ = board.set(pos, kind));
+        Self {
+            board,
+            state: view.state,
+            question_marks: view.question_marks,
+            first_click: view.first_click,
+            ..Self::with_seed(0, 0, view.mine_count, 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::{Mark, Pos};
+
+    #[test]
+    fn mines_hidden_while_playing() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0], vec![0, 0, 1]]);
+        game.open_cell(Pos { row: 0, col: 1 });
+
+        let view = game.player_view();
+        assert!(view.cells.iter().all(|kind| !kind.is_mine()));
+        assert_eq!(
+            Minesweeper::from_player_view(view).to_string(),
+            game.to_string()
+        );
+    }
+
+    #[test]
+    fn mines_shown_when_lost() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0], vec![0, 0, 1]]);
+        game.open_cell(Pos { row: 0, col: 0 });
+
+        let view = game.player_view();
+        assert_eq!(view.cells.iter().filter(|kind| kind.is_mine()).count(), 2);
+        assert_eq!(
+            Minesweeper::from_player_view(view).to_string(),
+            game.to_string()
+        );
+    }
+
+    #[test]
+    fn flags_kept() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0]]);
+        game.flag_cell(Pos { row: 0, col: 0 });
+
+        let view = game.player_view();
+        assert_eq!(view.cells[0], CellKind::Closed { mark: Mark::Flag });
+    }
+}
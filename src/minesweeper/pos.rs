@@ -1,4 +1,6 @@
-#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub struct Pos {
     pub row: u8,
     pub col: u8,
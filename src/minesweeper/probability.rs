@@ -0,0 +1,454 @@
+//! Exact mine probabilities for closed cells.
+//!
+//! Cells next to an open number (the frontier) are split into independent
+//! groups which are enumerated separately, recording how many consistent
+//! configurations use each number of mines. The remaining closed cells are
+//! interchangeable, so a frontier configuration using `m` mines is weighted by
+//! the number of ways to place the other `remaining - m` mines among them.
+//!
+//! Enumeration is exponential in the size of a group, so it gets a budget of
+//! search steps. Boards that need more get a coarse estimate instead, which
+//! is marked as not exact.
+
+use super::board::Board;
+use super::pos::Pos;
+use super::solver::{self, Constraint};
+use std::collections::{HashMap, HashSet};
+
+/// Search steps shared by every group before giving up on exact numbers.
+const SEARCH_BUDGET: usize = 200_000;
+
+/// Mine chances of the closed cells.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Probabilities {
+    pub cells: HashMap<Pos, f64>,
+    /// False when the search budget ran out and the frontier and interior
+    /// chances are estimates. Deduced cells are always exact.
+    pub exact: bool,
+}
+
+impl Probabilities {
+    fn exact(cells: HashMap<Pos, f64>) -> Self {
+        Probabilities { cells, exact: true }
+    }
+}
+
+/// Consistent configurations of one group of frontier cells, indexed by the
+/// number of mines they use.
+struct Group {
+    cells: Vec<Pos>,
+    /// `counts[k]`: configurations using `k` mines.
+    counts: Vec<f64>,
+    /// `cell_counts[k][i]`: configurations using `k` mines where `cells[i]`
+    /// is a mine.
+    cell_counts: Vec<Vec<f64>>,
+}
+
+pub(super) fn mine_probabilities(board: &Board, mine_count: usize) -> Probabilities {
+    probabilities_within(board, mine_count, SEARCH_BUDGET)
+}
+
+fn probabilities_within(board: &Board, mine_count: usize, budget: usize) -> Probabilities {
+    let deductions = solver::solve(board);
+    let constraints = solver::constraints(board, &deductions, None);
+
+    let mut probabilities: HashMap<_, _> = deductions
+        .safe
+        .iter()
+        .map(|&pos| (pos, 0.0))
+        .chain(deductions.mines.iter().map(|&pos| (pos, 1.0)))
+        .collect();
+
+    let frontier: HashSet<_> = constraints.iter().flat_map(|c| c.cells.iter()).collect();
+    let interior: Vec<_> = board
+        .iter_pos()
+        .filter(|pos| board.get(*pos).map_or(false, |kind| !kind.is_open()))
+        .filter(|pos| !probabilities.contains_key(pos) && !frontier.contains(pos))
+        .collect();
+    let remaining = match mine_count.checked_sub(deductions.mines.len()) {
+        Some(remaining) => remaining,
+        None => return Probabilities::exact(HashMap::new()),
+    };
+
+    let split = split_constraints(constraints);
+    let mut budget = budget;
+    let Some(groups) = split
+        .iter()
+        .map(|constraints| enumerate(constraints, &mut budget))
+        .collect::<Option<Vec<_>>>()
+    else {
+        let constraints = split.into_iter().flatten().collect::<Vec<_>>();
+        probabilities.extend(estimate(&constraints, &interior, remaining));
+        return Probabilities {
+            cells: probabilities,
+            exact: false,
+        };
+    };
+
+    let weights = &interior_weights(interior.len(), remaining);
+    let total = convolve_all(groups.iter().map(|g| &g.counts));
+    let z: f64 = total.iter().enumerate().map(|(m, n)| n * weights(m)).sum();
+    if z == 0.0 {
+        return Probabilities::exact(HashMap::new());
+    }
+
+    for (i, group) in groups.iter().enumerate() {
+        let others = convolve_all(
+            groups
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, g)| &g.counts),
+        );
+        for (cell_idx, &pos) in group.cells.iter().enumerate() {
+            let p: f64 = group
+                .cell_counts
+                .iter()
+                .enumerate()
+                .flat_map(|(k, cell_counts)| {
+                    others
+                        .iter()
+                        .enumerate()
+                        .map(move |(m, n)| cell_counts[cell_idx] * n * weights(k + m))
+                })
+                .sum();
+            probabilities.insert(pos, p / z);
+        }
+    }
+
+    if !interior.is_empty() {
+        let p: f64 = total
+            .iter()
+            .enumerate()
+            .filter(|&(m, _)| m <= remaining)
+            .map(|(m, n)| n * weights(m) * (remaining - m) as f64 / interior.len() as f64)
+            .sum();
+        probabilities.extend(interior.into_iter().map(|pos| (pos, p / z)));
+    }
+
+    Probabilities::exact(probabilities)
+}
+
+/// Splits constraints into groups that share no cells.
+fn split_constraints(constraints: Vec<Constraint>) -> Vec<Vec<Constraint>> {
+    let mut groups: Vec<Vec<Constraint>> = Vec::new();
+    for constraint in constraints {
+        let (mut joined, rest): (Vec<_>, Vec<_>) = groups.into_iter().partition(|group| {
+            group
+                .iter()
+                .any(|c| !c.cells.is_disjoint(&constraint.cells))
+        });
+        let mut group: Vec<_> = joined.drain(..).flatten().collect();
+        group.push(constraint);
+        groups = rest;
+        groups.push(group);
+    }
+    groups
+}
+
+/// Rough chances for when enumerating would take too long: each frontier
+/// cell gets the highest density of the constraints it's in, and the interior
+/// shares whatever mines the frontier is expected not to hold.
+fn estimate(constraints: &[Constraint], interior: &[Pos], remaining: usize) -> HashMap<Pos, f64> {
+    let mut probabilities: HashMap<Pos, f64> = HashMap::new();
+    for constraint in constraints {
+        let density = constraint.mines as f64 / constraint.cells.len() as f64;
+        for &pos in &constraint.cells {
+            let p = probabilities.entry(pos).or_default();
+            *p = p.max(density);
+        }
+    }
+
+    if !interior.is_empty() {
+        let frontier_mines: f64 = probabilities.values().sum();
+        let p = ((remaining as f64 - frontier_mines) / interior.len() as f64).clamp(0.0, 1.0);
+        probabilities.extend(interior.iter().map(|&pos| (pos, p)));
+    }
+    probabilities
+}
+
+/// Enumerates every mine assignment of a group's cells that satisfies all of
+/// its constraints, or gives up once `budget` search steps are used.
+fn enumerate(constraints: &[Constraint], budget: &mut usize) -> Option<Group> {
+    let mut cells: Vec<Pos> = Vec::new();
+    for constraint in constraints {
+        let mut new_cells: Vec<_> = constraint
+            .cells
+            .iter()
+            .filter(|pos| !cells.contains(pos))
+            .copied()
+            .collect();
+        new_cells.sort_by_key(|pos| (pos.row, pos.col));
+        cells.extend(new_cells);
+    }
+
+    let cell_constraints: Vec<Vec<usize>> = cells
+        .iter()
+        .map(|pos| {
+            constraints
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.cells.contains(pos))
+                .map(|(i, _)| i)
+                .collect()
+        })
+        .collect();
+
+    let mut search = Search {
+        constraints,
+        budget: *budget,
+        exhausted: false,
+        cell_constraints: &cell_constraints,
+        mines: vec![0; constraints.len()],
+        unassigned: constraints.iter().map(|c| c.cells.len()).collect(),
+        assignment: vec![false; cells.len()],
+        group: Group {
+            counts: vec![0.0; cells.len() + 1],
+            cell_counts: vec![vec![0.0; cells.len()]; cells.len() + 1],
+            cells,
+        },
+    };
+    search.run(0);
+    *budget = search.budget;
+    (!search.exhausted).then_some(search.group)
+}
+
+struct Search<'a> {
+    constraints: &'a [Constraint],
+    cell_constraints: &'a [Vec<usize>],
+    /// Steps left before the search gives up.
+    budget: usize,
+    exhausted: bool,
+    /// Mines assigned so far per constraint.
+    mines: Vec<usize>,
+    /// Cells not yet assigned per constraint.
+    unassigned: Vec<usize>,
+    assignment: Vec<bool>,
+    group: Group,
+}
+
+impl Search<'_> {
+    fn run(&mut self, idx: usize) {
+        if self.budget == 0 {
+            self.exhausted = true;
+            return;
+        }
+        self.budget -= 1;
+
+        if idx == self.assignment.len() {
+            let k = self.assignment.iter().filter(|&&mine| mine).count();
+            self.group.counts[k] += 1.0;
+            for (i, &mine) in self.assignment.iter().enumerate() {
+                if mine {
+                    self.group.cell_counts[k][i] += 1.0;
+                }
+            }
+            return;
+        }
+
+        for mine in [false, true] {
+            self.assign(idx, mine, true);
+            if self.is_consistent(idx) {
+                self.run(idx + 1);
+            }
+            self.assign(idx, mine, false);
+        }
+    }
+
+    fn assign(&mut self, idx: usize, mine: bool, apply: bool) {
+        self.assignment[idx] = mine && apply;
+        for &c in &self.cell_constraints[idx] {
+            if apply {
+                self.unassigned[c] -= 1;
+                self.mines[c] += mine as usize;
+            } else {
+                self.unassigned[c] += 1;
+                self.mines[c] -= mine as usize;
+            }
+        }
+    }
+
+    fn is_consistent(&self, idx: usize) -> bool {
+        self.cell_constraints[idx].iter().all(|&c| {
+            let needed = self.constraints[c].mines;
+            self.mines[c] <= needed && self.mines[c] + self.unassigned[c] >= needed
+        })
+    }
+}
+
+fn convolve_all<'a>(dists: impl Iterator<Item = &'a Vec<f64>>) -> Vec<f64> {
+    dists.fold(vec![1.0], |acc, dist| {
+        let mut out = vec![0.0; acc.len() + dist.len() - 1];
+        for (i, a) in acc.iter().enumerate() {
+            for (j, b) in dist.iter().enumerate() {
+                out[i + j] += a * b;
+            }
+        }
+        out
+    })
+}
+
+/// Weight of a frontier configuration using `m` mines: the number of ways to
+/// place the rest among the interior cells, scaled to stay within `f64`.
+fn interior_weights(interior: usize, remaining: usize) -> impl Fn(usize) -> f64 {
+    let ln_factorial: Vec<f64> = std::iter::once(0.0)
+        .chain((1..=interior).scan(0.0, |acc, n| {
+            *acc += (n as f64).ln();
+            Some(*acc)
+        }))
+        .collect();
+    let ln_choose =
+        move |k: usize| ln_factorial[interior] - ln_factorial[k] - ln_factorial[interior - k];
+    let max = (0..=interior.min(remaining))
+        .map(&ln_choose)
+        .fold(f64::MIN, f64::max);
+
+    move |m| match remaining.checked_sub(m) {
+        Some(k) if k <= interior => (ln_choose(k) - max).exp(),
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::{CellKind, Minesweeper};
+
+    /// Counts every placement of `mine_count` mines among the closed cells
+    /// that agrees with the open numbers.
+    fn brute_force(board: &Board, mine_count: usize) -> HashMap<Pos, f64> {
+        let hidden: Vec<_> = board
+            .iter_pos()
+            .filter(|&pos| !board.get(pos).unwrap().is_open())
+            .collect();
+        let mut totals = vec![0.0; hidden.len()];
+        let mut configurations = 0.0;
+        for mask in 0u32..(1 << hidden.len()) {
+            if mask.count_ones() as usize != mine_count {
+                continue;
+            }
+            let is_mine = |pos: Pos| {
+                hidden
+                    .iter()
+                    .position(|&p| p == pos)
+                    .map_or(false, |i| mask & (1 << i) != 0)
+            };
+            let consistent = board.iter_pos().all(|pos| match board.get(pos) {
+                Some(&CellKind::Open { neighbor_mines }) => {
+                    board.iter_neighbors(pos).filter(|&p| is_mine(p)).count()
+                        == neighbor_mines as usize
+                }
+                _ => true,
+            });
+            if consistent {
+                configurations += 1.0;
+                for (i, total) in totals.iter_mut().enumerate() {
+                    if mask & (1 << i) != 0 {
+                        *total += 1.0;
+                    }
+                }
+            }
+        }
+        hidden
+            .into_iter()
+            .zip(totals)
+            .map(|(pos, total)| (pos, total / configurations))
+            .collect()
+    }
+
+    fn assert_probabilities(actual: HashMap<Pos, f64>, expect: HashMap<Pos, f64>) {
+        assert_eq!(actual.len(), expect.len());
+        for (pos, p) in expect {
+            assert!(
+                (actual[&pos] - p).abs() < 1e-9,
+                "{:?}: {} != {}",
+                pos,
+                actual[&pos],
+                p
+            );
+        }
+    }
+
+    #[test]
+    fn uniform_without_information() {
+        let game = Minesweeper::new(3, 3, 3);
+        let probabilities = mine_probabilities(&game.board, 3);
+        assert!(probabilities.exact);
+        assert_eq!(probabilities.cells.len(), 9);
+        assert!(probabilities
+            .cells
+            .values()
+            .all(|&p| (p - 1.0 / 3.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn fifty_fifty() {
+        let mut game = Minesweeper::from_matrix(vec![vec![0, 1, 0, 0]]);
+        _ = game
+            .board
+            .set(Pos { row: 0, col: 2 }, CellKind::new_open(1));
+
+        let probabilities = mine_probabilities(&game.board, 1);
+        assert_probabilities(
+            probabilities.cells,
+            HashMap::from([
+                (Pos { row: 0, col: 0 }, 0.0),
+                (Pos { row: 0, col: 1 }, 0.5),
+                (Pos { row: 0, col: 3 }, 0.5),
+            ]),
+        );
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut game = Minesweeper::from_matrix(vec![
+            vec![0, 0, 0, 0],
+            vec![0, 0, 0, 1],
+            vec![1, 0, 0, 0],
+            vec![0, 1, 0, 1],
+        ]);
+        game.open_cell(Pos { row: 0, col: 0 });
+
+        let expect = brute_force(&game.board, game.mine_count);
+        assert_probabilities(mine_probabilities(&game.board, game.mine_count).cells, expect);
+    }
+
+    #[test]
+    fn estimates_past_the_budget() {
+        let mut game = Minesweeper::from_matrix(vec![
+            vec![0, 0, 0, 0],
+            vec![0, 0, 0, 1],
+            vec![1, 0, 0, 0],
+            vec![0, 1, 0, 1],
+        ]);
+        game.open_cell(Pos { row: 0, col: 0 });
+
+        let exact = mine_probabilities(&game.board, game.mine_count);
+        let estimate = probabilities_within(&game.board, game.mine_count, 1);
+        assert!(exact.exact);
+        assert!(!estimate.exact);
+        assert_eq!(estimate.cells.len(), exact.cells.len());
+        assert!(estimate.cells.values().all(|p| (0.0..=1.0).contains(p)));
+        // Deductions are still exact.
+        for (pos, p) in exact.cells {
+            if p == 0.0 || p == 1.0 {
+                assert_eq!(estimate.cells[&pos], p, "{:?}", pos);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_brute_force_with_separate_groups() {
+        let mut game = Minesweeper::from_matrix(vec![
+            vec![0, 0, 0, 0, 1],
+            vec![1, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0],
+            vec![0, 1, 0, 0, 1],
+        ]);
+        game.open_cell(Pos { row: 0, col: 2 });
+        game.open_cell(Pos { row: 3, col: 3 });
+
+        let expect = brute_force(&game.board, game.mine_count);
+        assert_probabilities(mine_probabilities(&game.board, game.mine_count).cells, expect);
+    }
+}
@@ -0,0 +1,334 @@
+//! RAW video files (`RawVF`), the text replay format shared by Minesweeper
+//! Arbiter, ViennaSweeper and most replay viewers.
+//!
+//! A header of `Key: Value` lines, the board after `Board:` (one row per
+//! line, `*` for a mine and `0` otherwise), then mouse events after
+//! `Events:`, one per line:
+//!
+//! ```text
+//! 0.310 rc 1 1 (8 8)
+//! ```
+//!
+//! That's the time in seconds, the event, the 1-based column and row, and
+//! the pointer's pixel position. Only button presses (`lc`, `rc`) and
+//! releases (`lr`, `rr`) matter for the game; moves and everything else are
+//! skipped on import.
+
+use super::{Action, FirstClickPolicy, GameState, Pos, Replay, ReplayEvent, SETTINGS};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Pixel size of a cell in the event coordinates.
+const SQUARE_SIZE: u32 = 16;
+
+#[derive(Debug, PartialEq)]
+pub enum RawError {
+    MissingField(&'static str),
+    BadField(&'static str),
+    BadBoard,
+    MineCount { expected: usize, found: usize },
+    BadEvent(String),
+}
+
+impl Error for RawError {}
+
+impl Display for RawError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RawError::MissingField(field) => write!(f, "missing {}", field),
+            RawError::BadField(field) => write!(f, "bad value for {}", field),
+            RawError::BadBoard => f.write_str("board doesn't match its size"),
+            RawError::MineCount { expected, found } => {
+                write!(f, "expected {} mines but found {}", expected, found)
+            }
+            RawError::BadEvent(line) => write!(f, "bad event '{}'", line),
+        }
+    }
+}
+
+impl Replay {
+    pub fn to_raw(&self) -> String {
+        let level = SETTINGS
+            .iter()
+            .find(|s| {
+                (s.height, s.width, s.mine_count) == (self.height, self.width, self.mine_count)
+            })
+            .map_or("Custom".to_string(), |s| s.difficulty.to_string());
+
+        let mut raw = String::new();
+        _ = writeln!(raw, "RawVF_Version: Rev5");
+        _ = writeln!(raw, "Program: rustwasm-sweeper");
+        _ = writeln!(raw, "Level: {}", level);
+        _ = writeln!(raw, "Width: {}", self.width);
+        _ = writeln!(raw, "Height: {}", self.height);
+        _ = writeln!(raw, "Mines: {}", self.mine_count);
+        _ = writeln!(raw, "Marks: Off");
+        _ = writeln!(raw, "Mode: Classic");
+        _ = writeln!(raw, "Board:");
+        for row in 0..self.height {
+            let line: String = (0..self.width)
+                .map(|col| match self.mines.contains(&Pos { row, col }) {
+                    true => '*',
+                    false => '0',
+                })
+                .collect();
+            _ = writeln!(raw, "{}", line);
+        }
+
+        _ = writeln!(raw, "Events:");
+        for event in &self.events {
+            let buttons: &[&str] = match event.action {
+                Action::Open(_) => &["lc", "lr"],
+                Action::Flag(_) => &["rc", "rr"],
+                Action::Chord(_) => &["lc", "rc", "lr", "rr"],
+            };
+            let (Action::Open(pos) | Action::Flag(pos) | Action::Chord(pos)) = event.action;
+            let (x, y) = (
+                pos.col as u32 * SQUARE_SIZE + SQUARE_SIZE / 2,
+                pos.row as u32 * SQUARE_SIZE + SQUARE_SIZE / 2,
+            );
+            for button in buttons {
+                _ = writeln!(
+                    raw,
+                    "{:.3} {} {} {} ({} {})",
+                    event.time as f64 / 1000.0,
+                    button,
+                    pos.col + 1,
+                    pos.row + 1,
+                    x,
+                    y
+                );
+            }
+        }
+        match self.game_at(self.events.len()).state {
+            GameState::Win => _ = writeln!(raw, "{:.3} won", self.duration() as f64 / 1000.0),
+            GameState::Lose => _ = writeln!(raw, "{:.3} boom", self.duration() as f64 / 1000.0),
+            _ => {}
+        }
+        raw
+    }
+
+    /// Reads a RAW video. Its layout doesn't come from a seed, so the replay
+    /// can be watched and played from but never restored or recorded.
+    pub fn from_raw(raw: &str) -> Result<Self, RawError> {
+        let mut lines = raw.lines().map(str::trim_end);
+
+        let header: HashMap<_, _> = lines
+            .by_ref()
+            .take_while(|&line| line != "Board:")
+            .filter_map(|line| line.split_once(':'))
+            .map(|(key, value)| (key.trim(), value.trim()))
+            .collect();
+        let width: u8 = header_field(&header, "Width")?;
+        let height: u8 = header_field(&header, "Height")?;
+        let mine_count: usize = header_field(&header, "Mines")?;
+
+        let mut mines = Vec::new();
+        for row in 0..height {
+            let line = lines.next().ok_or(RawError::BadBoard)?;
+            if line.chars().count() != width as usize {
+                return Err(RawError::BadBoard);
+            }
+            for (col, c) in line.chars().enumerate() {
+                match c {
+                    '*' => mines.push(Pos {
+                        row,
+                        col: col as u8,
+                    }),
+                    '0' => {}
+                    _ => return Err(RawError::BadBoard),
+                }
+            }
+        }
+        if mines.len() != mine_count {
+            return Err(RawError::MineCount {
+                expected: mine_count,
+                found: mines.len(),
+            });
+        }
+        if lines.next() != Some("Events:") {
+            return Err(RawError::MissingField("Events"));
+        }
+
+        let mut mouse = Mouse::default();
+        let mut events: Vec<ReplayEvent> = Vec::new();
+        for line in lines.filter(|line| !line.is_empty()) {
+            let bad_event = || RawError::BadEvent(line.to_string());
+            let fields: Vec<_> = line.split_whitespace().collect();
+            let (time, button) = match fields[..] {
+                [time, button @ ("lc" | "lr" | "rc" | "rr"), ..] => (time, button),
+                _ => continue,
+            };
+            let time: f64 = time.parse().map_err(|_| bad_event())?;
+            let pos = match fields[2..] {
+                [col, row, ..] => Pos {
+                    row: row.parse::<u8>().map_err(|_| bad_event())?.wrapping_sub(1),
+                    col: col.parse::<u8>().map_err(|_| bad_event())?.wrapping_sub(1),
+                },
+                _ => return Err(bad_event()),
+            };
+            if pos.row >= height || pos.col >= width {
+                return Err(bad_event());
+            }
+
+            if let Some(action) = mouse.press(button, pos) {
+                let time = (time * 1000.0).round().max(0.0) as u32;
+                events.push(ReplayEvent { time, action });
+            }
+        }
+        if let Some(start) = events.first().map(|event| event.time) {
+            events
+                .iter_mut()
+                .for_each(|event| event.time = event.time.saturating_sub(start));
+        }
+
+        Ok(Self {
+            height,
+            width,
+            mine_count,
+            seed: 0,
+            first_click: FirstClickPolicy::None,
+            question_marks: false,
+            mines,
+            events,
+        })
+    }
+}
+
+fn header_field<T: FromStr>(
+    header: &HashMap<&str, &str>,
+    name: &'static str,
+) -> Result<T, RawError> {
+    header
+        .get(name)
+        .ok_or(RawError::MissingField(name))?
+        .parse()
+        .map_err(|_| RawError::BadField(name))
+}
+
+/// Turns button presses and releases into actions: a right press flags, a
+/// left release opens, and pressing both chords on the first release.
+#[derive(Default)]
+struct Mouse {
+    left: bool,
+    right: bool,
+    chording: bool,
+}
+
+impl Mouse {
+    fn press(&mut self, button: &str, pos: Pos) -> Option<Action> {
+        let was_chording = self.chording;
+        match button {
+            "lc" => self.left = true,
+            "rc" => self.right = true,
+            "lr" => self.left = false,
+            _ => self.right = false,
+        }
+        if self.left && self.right {
+            self.chording = true;
+        }
+        if !self.left && !self.right {
+            self.chording = false;
+        }
+
+        match button {
+            "lr" | "rr" if was_chording && (self.left || self.right) => Some(Action::Chord(pos)),
+            "lr" | "rr" if was_chording => None,
+            "lr" => Some(Action::Open(pos)),
+            "rc" if !self.chording => Some(Action::Flag(pos)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::Minesweeper;
+
+    const CHORD: &str = include_str!("fixtures/chord.rawvf");
+
+    #[test]
+    fn load_fixture() {
+        let replay = Replay::from_raw(CHORD).unwrap();
+        assert_eq!((replay.height, replay.width, replay.mine_count), (4, 4, 2));
+        assert_eq!(
+            replay.mines,
+            vec![Pos { row: 0, col: 0 }, Pos { row: 3, col: 3 }]
+        );
+        assert_eq!(
+            replay.events,
+            vec![
+                ReplayEvent {
+                    time: 0,
+                    action: Action::Open(Pos { row: 1, col: 1 })
+                },
+                ReplayEvent {
+                    time: 310,
+                    action: Action::Flag(Pos { row: 0, col: 0 })
+                },
+                ReplayEvent {
+                    time: 710,
+                    action: Action::Chord(Pos { row: 1, col: 1 })
+                },
+            ]
+        );
+        assert_eq!(replay.game_at(3).state, GameState::Win);
+    }
+
+    #[test]
+    fn fixture_round_trip() {
+        let replay = Replay::from_raw(CHORD).unwrap();
+        let raw = replay.to_raw();
+        assert!(raw.contains("Board:\n*000\n0000\n0000\n000*\nEvents:\n"));
+        assert!(raw.ends_with("0.710 rr 2 2 (24 24)\n0.710 won\n"));
+        assert_eq!(Replay::from_raw(&raw).unwrap(), replay);
+    }
+
+    #[test]
+    fn played_game_round_trip() {
+        let mut game = Minesweeper::from_setting_with_seed(SETTINGS[1], 3);
+        game.apply_at(Action::Open(Pos { row: 8, col: 8 }), 200.0);
+        game.apply_at(Action::Flag(Pos { row: 0, col: 0 }), 1234.0);
+        game.apply_at(Action::Chord(Pos { row: 8, col: 8 }), 2000.0);
+        let replay = game.replay();
+
+        let raw = replay.to_raw();
+        assert!(raw.contains("Level: Intermediate\n"));
+        let imported = Replay::from_raw(&raw).unwrap();
+        assert_eq!(imported.mines, replay.mines);
+        assert_eq!(imported.events, replay.events);
+        assert!(imported.game_at(3).board == game.board);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let load = |raw: &str| Replay::from_raw(raw).err();
+        assert_eq!(
+            load(&CHORD.replace("Width: 4\n", "")),
+            Some(RawError::MissingField("Width"))
+        );
+        assert_eq!(
+            load(&CHORD.replace("Mines: 2", "Mines: two")),
+            Some(RawError::BadField("Mines"))
+        );
+        assert_eq!(
+            load(&CHORD.replace("000*", "00*")),
+            Some(RawError::BadBoard)
+        );
+        assert_eq!(
+            load(&CHORD.replace("000*", "0000")),
+            Some(RawError::MineCount {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            load(&CHORD.replace("0.310 rc 1 1", "0.310 rc 1 9")),
+            Some(RawError::BadEvent("0.310 rc 1 9 (8 8)".into()))
+        );
+    }
+}
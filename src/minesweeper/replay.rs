@@ -0,0 +1,289 @@
+use super::{Action, CellKind, FirstClickPolicy, GameState, Minesweeper, Pos};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::Display;
+
+/// Fastest pace, in milliseconds, at which a 3BV after the first can be
+/// cleared over a whole game. Record games stay well under 20 a second.
+pub const MIN_MS_PER_BBBV: u32 = 50;
+/// Fastest pace, in milliseconds, at which clicks can follow one another
+/// over a whole game.
+pub const MIN_MS_PER_CLICK: u32 = 40;
+
+/// Why a replay couldn't have been played.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReplayError {
+    WrongBoard,
+    WrongLayout,
+    BadTimestamps,
+    /// Won faster than anyone could click through it.
+    TooFast,
+}
+
+impl Error for ReplayError {}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::WrongBoard => f.write_str("actions fall off the board"),
+            ReplayError::WrongLayout => f.write_str("mines don't match the seed"),
+            ReplayError::BadTimestamps => f.write_str("event times go backwards"),
+            ReplayError::TooFast => f.write_str("replay is faster than anyone can play"),
+        }
+    }
+}
+
+/// An action and when it happened, in milliseconds since the first action.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    pub time: u32,
+    pub action: Action,
+}
+
+/// A recorded game: the board, where its mines were and every action taken.
+///
+/// Undo and redo aren't actions of their own. An undone action is dropped
+/// from `events` and a redone one is recorded again, so the replay is the line
+/// of play the game ended up with rather than every click along the way.
+/// Games that used undo aren't ranked, so a replay that counts never had
+/// anything taken back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub height: u8,
+    pub width: u8,
+    pub mine_count: usize,
+    pub seed: u64,
+    #[serde(default)]
+    pub first_click: FirstClickPolicy,
+    #[serde(default)]
+    pub question_marks: bool,
+    pub mines: Vec<Pos>,
+    /// The actions that stand, in order; undone ones aren't kept.
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    /// The game as it was after the first `step` events.
+    pub fn game_at(&self, step: usize) -> Minesweeper {
+        let mut game = Minesweeper::with_seed(self.height, self.width, self.mine_count, self.seed);
+        game.first_click = self.first_click;
+        game.question_marks = self.question_marks;
+        for &pos in &self.mines {
+            _ = game.board.set(pos, CellKind::new_mine());
+        }
+        game.state = GameState::Playing;
+
+        self.events
+            .iter()
+            .take(step)
+            .for_each(|event| game.apply_at(event.action, event.time as f64));
+        game
+    }
+
+    /// Rebuilds the game from a replay that didn't come from this process,
+    /// e.g. one read back from storage, checking it could have been played
+    /// and that its mines are the seed's. The result can be played on, undo
+    /// included.
+    pub fn restore(&self) -> Result<Minesweeper, ReplayError> {
+        let in_bounds = |pos: &Pos| pos.row < self.height && pos.col < self.width;
+        let mines: HashSet<_> = self.mines.iter().collect();
+        if !self.mines.iter().all(in_bounds)
+            || mines.len() != self.mines.len()
+            || mines.len() != self.mine_count
+        {
+            return Err(ReplayError::WrongLayout);
+        }
+        let in_bounds = |event: &ReplayEvent| match event.action {
+            Action::Open(pos) | Action::Flag(pos) | Action::Chord(pos) => in_bounds(&pos),
+        };
+        if !self.events.iter().all(in_bounds) {
+            return Err(ReplayError::WrongBoard);
+        }
+        self.check_layout()?;
+        if self.events.windows(2).any(|w| w[0].time > w[1].time) {
+            return Err(ReplayError::BadTimestamps);
+        }
+
+        Ok(self.game_at(self.events.len()))
+    }
+
+    /// Checks the mines are the ones the seed places for the first click, so
+    /// a layout can't be made up.
+    fn check_layout(&self) -> Result<(), ReplayError> {
+        if self.mine_count >= self.height as usize * self.width as usize {
+            return Err(ReplayError::WrongLayout);
+        }
+        let first_click = self.events.iter().find_map(|event| match event.action {
+            Action::Open(pos) => Some(pos),
+            _ => None,
+        });
+        let mut generated =
+            Minesweeper::with_seed(self.height, self.width, self.mine_count, self.seed);
+        generated.first_click = self.first_click;
+        generated.create_mines(first_click);
+        let mines: HashSet<_> = self.mines.iter().collect();
+        if mines.len() != self.mines.len() || mines != generated.replay().mines.iter().collect() {
+            return Err(ReplayError::WrongLayout);
+        }
+        Ok(())
+    }
+
+    /// Time of the last event.
+    pub fn duration(&self) -> u32 {
+        self.events.last().map_or(0, |event| event.time)
+    }
+
+    /// Checks the game took at least as long as a very fast player would
+    /// need for its 3BV and its clicks.
+    pub fn check_pace(&self) -> Result<(), ReplayError> {
+        let bbbv = self.game_at(0).board.bbbv() as u32;
+        let clicks = self.events.len() as u32;
+        let fastest = (bbbv.saturating_sub(1) * MIN_MS_PER_BBBV)
+            .max(clicks.saturating_sub(1) * MIN_MS_PER_CLICK);
+        if self.duration() < fastest {
+            return Err(ReplayError::TooFast);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::{solver, Setting, SETTINGS};
+
+    fn played_game() -> Minesweeper {
+        let mut game = Minesweeper::from_setting_with_seed(SETTINGS[0], 11);
+        game.apply_at(Action::Open(Pos { row: 4, col: 4 }), 1500.0);
+        game.apply_at(Action::Flag(Pos { row: 0, col: 0 }), 2250.0);
+        game.apply_at(Action::Open(Pos { row: 8, col: 8 }), 3000.0);
+        game
+    }
+
+    fn won_game() -> Minesweeper {
+        let setting = Setting {
+            first_click: FirstClickPolicy::NoGuess,
+            ..SETTINGS[0]
+        };
+        let mut game = Minesweeper::from_setting_with_seed(setting, 5);
+        let mut now = 100.0;
+        game.apply_at(Action::Open(Pos { row: 4, col: 4 }), now);
+        while game.state == GameState::Playing {
+            let safe = solver::solve_with_mine_count(&game.board, game.mine_count).safe;
+            for pos in safe {
+                now += 250.0;
+                game.apply_at(Action::Open(pos), now);
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn records_relative_times() {
+        let replay = played_game().replay();
+        let times: Vec<_> = replay.events.iter().map(|e| e.time).collect();
+        assert_eq!(times, vec![0, 750, 1500]);
+        assert_eq!(replay.duration(), 1500);
+        assert_eq!(replay.mines.len(), 10);
+    }
+
+    #[test]
+    fn game_at_end_matches_game() {
+        let game = played_game();
+        let replay = game.replay();
+        let replayed = replay.game_at(replay.events.len());
+        assert!(replayed.board == game.board);
+        assert_eq!(replayed.state, game.state);
+        assert_eq!(replayed.replay(), replay);
+    }
+
+    #[test]
+    fn game_at_start_is_closed() {
+        let replay = played_game().replay();
+        let game = replay.game_at(0);
+        assert!(game.board.iter().flatten().all(|kind| !kind.is_open()));
+    }
+
+    #[test]
+    fn undo_drops_events() {
+        let mut game = played_game();
+        game.undo();
+        assert_eq!(game.replay().events.len(), 2);
+        game.redo();
+        assert_eq!(game.replay().duration(), 1500);
+    }
+
+    #[test]
+    fn restore_plays_on() {
+        let mut game = Minesweeper::from_setting_with_seed(SETTINGS[0], 11);
+        game.apply_at(Action::Open(Pos { row: 4, col: 4 }), 1500.0);
+        game.apply_at(Action::Flag(Pos { row: 0, col: 0 }), 3000.0);
+        assert_eq!(game.state, GameState::Playing);
+        let mut restored = game.replay().restore().unwrap();
+        assert!(restored.board == game.board);
+        assert_eq!(restored.state, game.state);
+
+        let mine = restored
+            .board
+            .iter_pos()
+            .find(|&pos| restored.board.get(pos) == Some(&CellKind::new_mine()))
+            .unwrap();
+        restored.resume_clock(10_000.0, 1500.0);
+        restored.apply_at(Action::Flag(mine), 10_500.0);
+        assert_eq!(restored.replay().duration(), 2000);
+        assert!(restored.undo());
+        assert!(restored.board == game.board);
+    }
+
+    #[test]
+    fn restore_rejects_corrupt_replays() {
+        let mut replay = played_game().replay();
+        replay.mines[1] = replay.mines[0];
+        assert_eq!(replay.restore().err(), Some(ReplayError::WrongLayout));
+
+        let mut replay = played_game().replay();
+        replay.mines[0] = Pos { row: 9, col: 0 };
+        assert_eq!(replay.restore().err(), Some(ReplayError::WrongLayout));
+
+        let mut replay = played_game().replay();
+        replay.events[0].action = Action::Open(Pos { row: 0, col: 9 });
+        assert_eq!(replay.restore().err(), Some(ReplayError::WrongBoard));
+
+        // A layout that isn't the seed's, e.g. edited in storage.
+        let mut replay = played_game().replay();
+        let free = (0..9)
+            .flat_map(|row| (0..9).map(move |col| Pos { row, col }))
+            .find(|pos| !replay.mines.contains(pos))
+            .unwrap();
+        replay.mines[0] = free;
+        assert_eq!(replay.restore().err(), Some(ReplayError::WrongLayout));
+    }
+
+    #[test]
+    fn pace_of_a_played_win() {
+        let replay = won_game().replay();
+        assert_eq!(replay.check_pace(), Ok(()));
+    }
+
+    #[test]
+    fn pace_too_fast() {
+        let mut replay = won_game().replay();
+        replay.events.iter_mut().for_each(|event| event.time = 1);
+        assert_eq!(replay.check_pace(), Err(ReplayError::TooFast));
+    }
+
+    #[test]
+    fn restore_rejects_bad_timestamps() {
+        let mut replay = won_game().replay();
+        replay.events[1].time = replay.duration() + 1;
+        assert_eq!(replay.restore().err(), Some(ReplayError::BadTimestamps));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        let replay = played_game().replay();
+        let json = serde_json::to_string(&replay).unwrap();
+        assert_eq!(serde_json::from_str::<Replay>(&json).unwrap(), replay);
+    }
+}
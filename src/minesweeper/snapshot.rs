@@ -0,0 +1,354 @@
+//! A saved game, as JSON.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "height": 2,
+//!   "width": 4,
+//!   "mine_count": 3,
+//!   "state": "Playing",
+//!   "elapsed": 12,
+//!   "rows": ["*2F.", "12*!"]
+//! }
+//! ```
+//!
+//! `elapsed` is in seconds and `state` is one of `Unstarted`, `Playing`,
+//! `Win` or `Lose`. Each row has one character per cell:
+//!
+//! | char     | cell                                 |
+//! |----------|--------------------------------------|
+//! | `0`..`8` | open, with that many mines around it |
+//! | `.`      | closed                               |
+//! | `F`      | closed and flagged                   |
+//! | `?`      | closed and question-marked           |
+//! | `*`      | mine                                 |
+//! | `!`      | mine and flagged                     |
+//! | `Q`      | mine and question-marked             |
+//!
+//! Mines aren't placed until the first click, so an `Unstarted` snapshot
+//! usually has none, unless it's a fixed layout such as one loaded from a
+//! board file. Any other has exactly `mine_count`. Either way at least one
+//! cell has to be left free of mines.
+
+use super::{Board, CellKind, GameState, Mark, Minesweeper, Pos};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Display;
+
+const VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq)]
+pub enum SnapshotError {
+    Json(String),
+    UnknownVersion(u32),
+    WrongSize,
+    BadCell(char),
+    MineCount {
+        expected: usize,
+        found: usize,
+    },
+    /// More mines than the board has room for next to a safe first click.
+    TooManyMines(usize),
+    WrongNumber(Pos),
+    WrongState,
+}
+
+impl Error for SnapshotError {}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Json(e) => write!(f, "not a snapshot: {}", e),
+            SnapshotError::UnknownVersion(v) => write!(f, "unknown snapshot version {}", v),
+            SnapshotError::WrongSize => f.write_str("rows don't match the board size"),
+            SnapshotError::BadCell(c) => write!(f, "unknown cell '{}'", c),
+            SnapshotError::MineCount { expected, found } => {
+                write!(f, "expected {} mines but found {}", expected, found)
+            }
+            SnapshotError::TooManyMines(count) => {
+                write!(f, "{} mines don't fit on the board", count)
+            }
+            SnapshotError::WrongNumber(pos) => {
+                write!(f, "number at {:?} doesn't match its mines", pos)
+            }
+            SnapshotError::WrongState => f.write_str("game state doesn't match the board"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    height: u8,
+    width: u8,
+    mine_count: usize,
+    state: GameState,
+    elapsed: u16,
+    rows: Vec<String>,
+}
+
+impl CellKind {
+    fn to_snapshot_char(self) -> char {
+        match self {
+            CellKind::Open { neighbor_mines } => (b'0' + neighbor_mines) as char,
+            CellKind::Closed { mark: Mark::None } => '.',
+            CellKind::Closed { mark: Mark::Flag } => 'F',
+            CellKind::Mine { mark: Mark::None } => '*',
+            CellKind::Mine { mark: Mark::Flag } => '!',
+            CellKind::Closed {
+                mark: Mark::Question,
+            } => '?',
+            CellKind::Mine {
+                mark: Mark::Question,
+            } => 'Q',
+        }
+    }
+
+    fn from_snapshot_char(c: char) -> Result<Self, SnapshotError> {
+        match c {
+            '0'..='8' => Ok(CellKind::new_open(c as u8 - b'0')),
+            '.' => Ok(CellKind::Closed { mark: Mark::None }),
+            'F' => Ok(CellKind::Closed { mark: Mark::Flag }),
+            '*' => Ok(CellKind::Mine { mark: Mark::None }),
+            '!' => Ok(CellKind::Mine { mark: Mark::Flag }),
+            '?' => Ok(CellKind::Closed {
+                mark: Mark::Question,
+            }),
+            'Q' => Ok(CellKind::Mine {
+                mark: Mark::Question,
+            }),
+            c => Err(SnapshotError::BadCell(c)),
+        }
+    }
+}
+
+impl Minesweeper {
+    /// Saves the game along with `elapsed` seconds on the timer.
+    pub fn to_snapshot(&self, elapsed: u16) -> String {
+        let snapshot = Snapshot {
+            version: VERSION,
+            height: self.board.height,
+            width: self.board.width,
+            mine_count: self.mine_count,
+            state: self.state,
+            elapsed,
+            rows: self
+                .board
+                .iter()
+                .map(|row| row.iter().map(|kind| kind.to_snapshot_char()).collect())
+                .collect(),
+        };
+        serde_json::to_string_pretty(&snapshot).expect("snapshot serializes")
+    }
+
+    /// Loads a game saved by [`Minesweeper::to_snapshot`] and its elapsed
+    /// seconds. The board has to be one the game could have reached.
+    pub fn from_snapshot(snapshot: &str) -> Result<(Self, u16), SnapshotError> {
+        let snapshot: Snapshot =
+            serde_json::from_str(snapshot).map_err(|e| SnapshotError::Json(e.to_string()))?;
+        if snapshot.version != VERSION {
+            return Err(SnapshotError::UnknownVersion(snapshot.version));
+        }
+        if snapshot.rows.len() != snapshot.height as usize
+            || snapshot
+                .rows
+                .iter()
+                .any(|row| row.chars().count() != snapshot.width as usize)
+        {
+            return Err(SnapshotError::WrongSize);
+        }
+
+        let mut board = Board::new(snapshot.height, snapshot.width);
+        for (row, line) in snapshot.rows.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                let pos = Pos {
+                    row: row as u8,
+                    col: col as u8,
+                };
+                _ = board.set(pos, CellKind::from_snapshot_char(c)?);
+            }
+        }
+        validate(&board, snapshot.mine_count, snapshot.state)?;
+
+        let game = Self {
+            board,
+            state: snapshot.state,
+            restored: true,
+            ..Self::with_seed(0, 0, snapshot.mine_count, rand::random())
+        };
+        Ok((game, snapshot.elapsed))
+    }
+}
+
+fn validate(board: &Board, mine_count: usize, state: GameState) -> Result<(), SnapshotError> {
+    let kinds = || board.iter().flatten();
+    if mine_count >= kinds().count() {
+        return Err(SnapshotError::TooManyMines(mine_count));
+    }
+    let found = kinds().filter(|kind| kind.is_mine()).count();
+    if state == GameState::Unstarted && found == 0 {
+        return match kinds().all(|kind| kind.is_closed()) {
+            true => Ok(()),
+            false => Err(SnapshotError::WrongState),
+        };
+    }
+    if found != mine_count {
+        return Err(SnapshotError::MineCount {
+            expected: mine_count,
+            found,
+        });
+    }
+
+    for pos in board.iter_pos() {
+        if let Some(&CellKind::Open { neighbor_mines }) = board.get(pos) {
+            let mines = board
+                .iter_neighbors(pos)
+                .filter(|&p| board.get(p).map_or(false, |kind| kind.is_mine()))
+                .count();
+            if mines != neighbor_mines as usize {
+                return Err(SnapshotError::WrongNumber(pos));
+            }
+        }
+    }
+
+    let cleared = !kinds().any(|kind| kind.is_closed());
+    let opened = kinds().any(|kind| kind.is_open());
+    if cleared != (state == GameState::Win) || (opened && state == GameState::Unstarted) {
+        return Err(SnapshotError::WrongState);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(game: &Minesweeper, elapsed: u16) -> Minesweeper {
+        let (restored, restored_elapsed) =
+            Minesweeper::from_snapshot(&game.to_snapshot(elapsed)).unwrap();
+        assert_eq!(restored_elapsed, elapsed);
+        assert!(restored.board == game.board);
+        assert_eq!(restored.state, game.state);
+        assert_eq!(restored.mine_count, game.mine_count);
+        restored
+    }
+
+    #[test]
+    fn round_trip_every_cell_kind() {
+        let mut game =
+            Minesweeper::from_matrix(vec![vec![1, 0, 0, 0], vec![0, 0, 0, 0], vec![0, 0, 0, 1]]);
+        game.open_cell(Pos { row: 2, col: 0 });
+        game.flag_cell(Pos { row: 0, col: 0 });
+        game.flag_cell(Pos { row: 0, col: 3 });
+        assert_eq!(game.to_string(), "!xx!\n111x\n001x\n");
+
+        let restored = round_trip(&game, 42);
+        assert_eq!(restored.to_string(), game.to_string());
+        assert!(game.to_snapshot(42).contains(
+            r#""rows": [
+    "!..F",
+    "111.",
+    "001*"
+  ]"#
+        ));
+    }
+
+    #[test]
+    fn round_trip_question_marks() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0]]);
+        game.question_marks = true;
+        for pos in [Pos { row: 0, col: 0 }, Pos { row: 0, col: 2 }] {
+            game.flag_cell(pos);
+            game.flag_cell(pos);
+        }
+        assert!(game.to_snapshot(0).contains(r#""Q.?""#));
+        assert!(round_trip(&game, 0).board == game.board);
+    }
+
+    #[test]
+    fn round_trip_states() {
+        let unstarted = Minesweeper::new(3, 4, 2);
+        round_trip(&unstarted, 0);
+
+        let layout = Minesweeper::from_layout(2, 2, &[Pos { row: 0, col: 1 }]);
+        round_trip(&layout, 0);
+
+        let mut lost = Minesweeper::from_matrix(vec![vec![1, 0, 0]]);
+        lost.open_cell(Pos { row: 0, col: 0 });
+        round_trip(&lost, 3);
+
+        let mut won = Minesweeper::from_matrix(vec![vec![1, 0, 0]]);
+        won.open_cell(Pos { row: 0, col: 2 });
+        assert_eq!(won.state, GameState::Win);
+        round_trip(&won, 7);
+    }
+
+    #[test]
+    fn restored_game_plays_on() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0], vec![0, 0, 0]]);
+        game.open_cell(Pos { row: 1, col: 2 });
+        let (mut restored, _) = Minesweeper::from_snapshot(&game.to_snapshot(0)).unwrap();
+
+        restored.open_cell(Pos { row: 1, col: 0 });
+        assert_eq!(restored.state, GameState::Win);
+        assert!(!restored.is_ranked());
+    }
+
+    fn snapshot(state: &str, mine_count: usize, rows: &[&str]) -> String {
+        serde_json::json!({
+            "version": 1,
+            "height": rows.len(),
+            "width": rows[0].len(),
+            "mine_count": mine_count,
+            "state": state,
+            "elapsed": 0,
+            "rows": rows,
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn full_unstarted_snapshot_plays() {
+        let (mut game, _) =
+            Minesweeper::from_snapshot(&snapshot("Unstarted", 5, &["...", "..."])).unwrap();
+        game.open_cell(Pos { row: 0, col: 0 });
+        assert_eq!(game.state, GameState::Win);
+    }
+
+    #[test]
+    fn rejects_bad_snapshots() {
+        let load = |s: String| Minesweeper::from_snapshot(&s).map(|_| ());
+        assert!(matches!(load("{}".into()), Err(SnapshotError::Json(_))));
+        assert_eq!(
+            load(snapshot("Playing", 1, &["*1", "1"])),
+            Err(SnapshotError::WrongSize)
+        );
+        assert_eq!(
+            load(snapshot("Playing", 1, &["*1#"])),
+            Err(SnapshotError::BadCell('#'))
+        );
+        assert_eq!(
+            load(snapshot("Playing", 2, &["*1."])),
+            Err(SnapshotError::MineCount {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            load(snapshot("Unstarted", 1000, &["...", "...", "..."])),
+            Err(SnapshotError::TooManyMines(1000))
+        );
+        assert_eq!(
+            load(snapshot("Playing", 1, &["*2."])),
+            Err(SnapshotError::WrongNumber(Pos { row: 0, col: 1 }))
+        );
+        assert_eq!(
+            load(snapshot("Win", 1, &["*1."])),
+            Err(SnapshotError::WrongState)
+        );
+        assert_eq!(
+            load(snapshot("Unstarted", 1, &["*1."])),
+            Err(SnapshotError::WrongState)
+        );
+    }
+}
@@ -0,0 +1,231 @@
+//! Deductions over the part of a [`Board`] the player can see.
+//!
+//! Every open cell gives a constraint: its closed neighbors hold exactly
+//! `neighbor_mines` mines. Constraints are resolved on their own (all closed
+//! neighbors are safe or all are mines) and pairwise, which covers subset
+//! patterns such as 1-2-1 and 1-1 along an edge.
+
+use super::board::Board;
+use super::cell::CellKind;
+use super::pos::Pos;
+use std::collections::HashSet;
+
+/// Cells that can be proven safe or proven to be mines.
+#[derive(Debug, Default, PartialEq)]
+pub struct Deductions {
+    pub safe: HashSet<Pos>,
+    pub mines: HashSet<Pos>,
+}
+
+impl Deductions {
+    fn is_known(&self, pos: &Pos) -> bool {
+        self.safe.contains(pos) || self.mines.contains(pos)
+    }
+
+    fn len(&self) -> usize {
+        self.safe.len() + self.mines.len()
+    }
+}
+
+/// A group of unresolved cells holding exactly `mines` mines.
+#[derive(Debug)]
+pub(super) struct Constraint {
+    pub cells: HashSet<Pos>,
+    pub mines: usize,
+}
+
+/// Deduces safe cells and mines from the open cells of `board`.
+///
+/// Only what the player can see is used: open cells and their counts. Mines
+/// and closed cells are indistinguishable, and flags are ignored since they
+/// are the player's guesses.
+pub fn solve(board: &Board) -> Deductions {
+    deduce(board, None)
+}
+
+/// Like [`solve`], but also uses the total number of mines on the board,
+/// which settles some endgames the local counts can't.
+pub fn solve_with_mine_count(board: &Board, mine_count: usize) -> Deductions {
+    deduce(board, Some(mine_count))
+}
+
+fn deduce(board: &Board, mine_count: Option<usize>) -> Deductions {
+    let mut deductions = Deductions::default();
+
+    loop {
+        let known = deductions.len();
+        let constraints = constraints(board, &deductions, mine_count);
+
+        for constraint in &constraints {
+            if constraint.mines == 0 {
+                deductions.safe.extend(&constraint.cells);
+            } else if constraint.mines == constraint.cells.len() {
+                deductions.mines.extend(&constraint.cells);
+            }
+        }
+        if deductions.len() != known {
+            continue;
+        }
+
+        for (i, a) in constraints.iter().enumerate() {
+            for b in &constraints[i + 1..] {
+                if a.cells.is_disjoint(&b.cells) {
+                    continue;
+                }
+                resolve_pair(a, b, &mut deductions);
+                resolve_pair(b, a, &mut deductions);
+            }
+        }
+        if deductions.len() == known {
+            return deductions;
+        }
+    }
+}
+
+/// If `b` needs as many more mines than `a` as it has cells outside of `a`,
+/// those cells are all mines and `a`'s cells outside of `b` are all safe.
+fn resolve_pair(a: &Constraint, b: &Constraint, deductions: &mut Deductions) {
+    let only_b: Vec<_> = b.cells.difference(&a.cells).collect();
+    if b.mines < a.mines || b.mines - a.mines != only_b.len() {
+        return;
+    }
+    deductions.mines.extend(only_b);
+    deductions.safe.extend(a.cells.difference(&b.cells));
+}
+
+/// Builds one constraint per open cell with unresolved neighbors, plus one
+/// over every unresolved cell when the total mine count is known.
+pub(super) fn constraints(
+    board: &Board,
+    deductions: &Deductions,
+    mine_count: Option<usize>,
+) -> Vec<Constraint> {
+    let is_hidden = |pos: Pos| board.get(pos).map_or(false, |kind| !kind.is_open());
+
+    let mut constraints: Vec<_> = board
+        .iter_pos()
+        .filter_map(|pos| match board.get(pos) {
+            Some(&CellKind::Open { neighbor_mines }) => Some((pos, neighbor_mines as usize)),
+            _ => None,
+        })
+        .filter_map(|(pos, neighbor_mines)| {
+            let hidden: Vec<_> = board
+                .iter_neighbors(pos)
+                .filter(|&p| is_hidden(p))
+                .collect();
+            let known_mines = hidden
+                .iter()
+                .filter(|p| deductions.mines.contains(p))
+                .count();
+            let cells: HashSet<_> = hidden
+                .into_iter()
+                .filter(|p| !deductions.is_known(p))
+                .collect();
+            if cells.is_empty() {
+                return None;
+            }
+            Some(Constraint {
+                cells,
+                mines: neighbor_mines.saturating_sub(known_mines),
+            })
+        })
+        .collect();
+
+    if let Some(mine_count) = mine_count {
+        let cells: HashSet<_> = board
+            .iter_pos()
+            .filter(|&p| is_hidden(p) && !deductions.is_known(&p))
+            .collect();
+        if !cells.is_empty() {
+            constraints.push(Constraint {
+                cells,
+                mines: mine_count.saturating_sub(deductions.mines.len()),
+            });
+        }
+    }
+
+    constraints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::Minesweeper;
+
+    #[test]
+    fn solve_single_cell() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0, 0]]);
+        game.open_cell(Pos { row: 0, col: 3 });
+
+        let deductions = solve(&game.board);
+        assert_eq!(deductions.mines, HashSet::from([Pos { row: 0, col: 0 }]));
+        assert!(deductions.safe.is_empty());
+    }
+
+    #[test]
+    fn solve_ignores_flags() {
+        let mut game = Minesweeper::from_matrix(vec![vec![1, 0, 0], vec![0, 0, 0], vec![0, 0, 0]]);
+        game.flag_cell(Pos { row: 1, col: 0 });
+        game.open_cell(Pos { row: 2, col: 2 });
+
+        let deductions = solve(&game.board);
+        assert_eq!(deductions.safe, HashSet::from([Pos { row: 1, col: 0 }]));
+        assert_eq!(deductions.mines, HashSet::from([Pos { row: 0, col: 0 }]));
+    }
+
+    #[test]
+    fn solve_one_two_one() {
+        let mut game = Minesweeper::from_matrix(vec![vec![0, 0, 0], vec![0, 0, 0], vec![1, 0, 1]]);
+        game.open_cell(Pos { row: 0, col: 1 });
+
+        let deductions = solve(&game.board);
+        assert_eq!(
+            deductions.mines,
+            HashSet::from([Pos { row: 2, col: 0 }, Pos { row: 2, col: 2 }])
+        );
+        assert_eq!(deductions.safe, HashSet::from([Pos { row: 2, col: 1 }]));
+    }
+
+    #[test]
+    fn solve_chains_deductions() {
+        let mut game = Minesweeper::from_matrix(vec![
+            vec![0, 0, 0, 0],
+            vec![0, 0, 0, 0],
+            vec![1, 0, 0, 0],
+            vec![0, 0, 0, 1],
+        ]);
+        game.open_cell(Pos { row: 0, col: 3 });
+
+        let deductions = solve(&game.board);
+        assert!(deductions.mines.contains(&Pos { row: 2, col: 0 }));
+        assert!(deductions.safe.contains(&Pos { row: 3, col: 0 }));
+    }
+
+    #[test]
+    fn solve_with_mine_count_settles_endgame() {
+        let mut game = Minesweeper::from_matrix(vec![vec![0, 1, 0, 0]]);
+        _ = game
+            .board
+            .set(Pos { row: 0, col: 2 }, CellKind::new_open(1));
+
+        assert_eq!(solve(&game.board), Deductions::default());
+        let deductions = solve_with_mine_count(&game.board, 1);
+        assert_eq!(deductions.safe, HashSet::from([Pos { row: 0, col: 0 }]));
+        assert!(deductions.mines.is_empty());
+    }
+
+    #[test]
+    fn solve_never_reads_mines() {
+        let mut game = Minesweeper::from_matrix(vec![vec![0, 0, 0], vec![0, 0, 0], vec![1, 0, 1]]);
+        game.open_cell(Pos { row: 0, col: 1 });
+        let expect = solve(&game.board);
+
+        _ = game
+            .board
+            .set(Pos { row: 2, col: 0 }, CellKind::new_closed());
+        _ = game
+            .board
+            .set(Pos { row: 2, col: 2 }, CellKind::new_closed());
+        assert_eq!(solve(&game.board), expect);
+    }
+}
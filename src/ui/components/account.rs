@@ -0,0 +1,469 @@
+use crate::ui::shared::{GameUpdater, MouseBindings};
+use leptos::*;
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use actix_web::HttpRequest;
+        use sqlx::{Pool, Sqlite};
+
+        const SESSION_COOKIE: &str = "session";
+        /// How long a login lasts, in days.
+        const LOGIN_DAYS: i64 = 30;
+        const MIN_PASSWORD_LEN: usize = 8;
+        const MAX_NAME_LEN: usize = 20;
+        /// How long a claim code works for, in days.
+        const CLAIM_DAYS: i64 = 7;
+        /// Checked against when a name doesn't exist, so logging in takes as
+        /// long either way. Made with `Argon2::default()`, like real hashes.
+        const DUMMY_HASH: &str =
+            "$argon2id$v=19$m=19456,t=2,p=1$ZZY8KN6P29aQQ5R/Dvll7w$Icm5vbsTTEIGhyBndkF4y3ua1y5VoReBcn4oQ7VT4ho";
+
+        /// A player who has logged in.
+        pub struct Player {
+            pub id: i64,
+            pub name: String,
+        }
+
+        fn request(cx: Scope) -> Result<HttpRequest, ServerFnError> {
+            use_context::<HttpRequest>(cx).ok_or(ServerFnError::ServerError("no request".into()))
+        }
+
+        fn db(req: &HttpRequest) -> Result<Pool<Sqlite>, ServerFnError> {
+            use crate::AppState;
+            use actix_web::web;
+
+            req.app_data::<web::Data<AppState>>()
+                .map(|app_state| app_state.db_pool.clone())
+                .ok_or(ServerFnError::ServerError("no app state".into()))
+        }
+
+        fn check_password(password: &str) -> Result<(), String> {
+            match password.chars().count() < MIN_PASSWORD_LEN {
+                true => Err(format!("passwords are at least {} characters", MIN_PASSWORD_LEN)),
+                false => Ok(()),
+            }
+        }
+
+        fn hash_password(password: &str) -> Result<String, ServerFnError> {
+            use argon2::password_hash::{PasswordHasher, SaltString};
+            use argon2::Argon2;
+
+            let salt = SaltString::generate(&mut rand::rngs::OsRng);
+            Argon2::default()
+                .hash_password(password.as_bytes(), &salt)
+                .map(|hash| hash.to_string())
+                .map_err(|e| ServerFnError::ServerError(e.to_string()))
+        }
+
+        fn verify_password(password: &str, hash: &str) -> bool {
+            use argon2::password_hash::{PasswordHash, PasswordVerifier};
+            use argon2::Argon2;
+
+            PasswordHash::new(hash)
+                .and_then(|hash| Argon2::default().verify_password(password.as_bytes(), &hash))
+                .is_ok()
+        }
+
+        fn set_session_cookie(cx: Scope, token: &str, max_age: i64) -> Result<(), ServerFnError> {
+            use actix_web::http::header::{HeaderValue, SET_COOKIE};
+            use leptos_actix::ResponseOptions;
+
+            let response = use_context::<ResponseOptions>(cx)
+                .ok_or(ServerFnError::ServerError("no response".into()))?;
+            let cookie = format!(
+                "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax; Secure",
+                SESSION_COOKIE,
+                token,
+                max_age
+            );
+            let cookie = HeaderValue::from_str(&cookie)
+                .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+            response.insert_header(SET_COOKIE, cookie);
+            Ok(())
+        }
+
+        /// Starts a login for `player_id` and hands its cookie to the browser.
+        async fn log_in(cx: Scope, db: &Pool<Sqlite>, player_id: i64) -> Result<(), ServerFnError> {
+            let token = format!("{:032x}", rand::random::<u128>());
+            sqlx::query!(
+                "INSERT INTO login_session (token, player_id) VALUES (?, ?)",
+                token,
+                player_id
+            )
+            .execute(db)
+            .await
+            .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+            set_session_cookie(cx, &token, LOGIN_DAYS * 24 * 60 * 60)
+        }
+
+        /// The player logged in on this request, if any.
+        pub(crate) async fn current_player(req: &HttpRequest) -> Result<Option<Player>, ServerFnError> {
+            let Some(token) = req.cookie(SESSION_COOKIE) else {
+                return Ok(None);
+            };
+            let token = token.value().to_owned();
+            let expiry = format!("-{} days", LOGIN_DAYS);
+            let player = sqlx::query_as!(
+                Player,
+                r#"
+            SELECT
+                p.id AS "id!",
+                p.name AS "name!"
+            FROM
+                login_session AS s
+            INNER JOIN player AS p ON p.id = s.player_id
+            WHERE
+                s.token = ?
+                AND s.created_at > datetime('now', ?)
+            "#,
+                token,
+                expiry
+            )
+            .fetch_optional(&db(req)?)
+            .await
+            .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+            Ok(player)
+        }
+    }
+}
+
+/// Name of the logged-in player.
+#[server(CurrentPlayer, "/api")]
+pub async fn current_player_name(cx: Scope) -> Result<Option<String>, ServerFnError> {
+    let req = request(cx)?;
+    Ok(current_player(&req).await?.map(|player| player.name))
+}
+
+/// Creates an account and logs into it. Names on the leaderboard from before
+/// accounts existed can't be registered; their owners claim them with a code
+/// instead. Names differing only in case count as the same name.
+#[server(Register, "/api")]
+pub async fn register(
+    cx: Scope,
+    name: String,
+    password: String,
+) -> Result<Result<String, String>, ServerFnError> {
+    use actix_web::web;
+
+    let name = name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Ok(Err(format!("names are 1 to {} characters", MAX_NAME_LEN)));
+    }
+    if let Err(e) = check_password(&password) {
+        return Ok(Err(e));
+    }
+
+    let req = request(cx)?;
+    let db = db(&req)?;
+    // Hashing is slow on purpose, so it runs on a blocking thread.
+    let hash = web::block(move || hash_password(&password))
+        .await
+        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))??;
+    // One statement, so a look-alike can't be inserted between the check and
+    // the insert. Names from before accounts may already differ only in
+    // case, which rules out a case-insensitive unique index.
+    let inserted = sqlx::query!(
+        "
+    INSERT INTO player (name, password_hash)
+    SELECT ?, ?
+    WHERE NOT EXISTS (SELECT 1 FROM player WHERE name = ? COLLATE NOCASE)
+    ",
+        name,
+        hash,
+        name
+    )
+    .execute(&db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    if inserted.rows_affected() == 0 {
+        let unclaimed = sqlx::query!(
+            r#"SELECT password_hash IS NULL AS "unclaimed!: bool" FROM player WHERE name = ? COLLATE NOCASE"#,
+            name
+        )
+        .fetch_optional(&db)
+        .await
+        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?
+        .map_or(false, |player| player.unclaimed);
+        return Ok(Err(match unclaimed {
+            true => format!("{} is from before accounts; claim it with a code", name),
+            false => format!("{} is taken", name),
+        }));
+    }
+
+    log_in(cx, &db, inserted.last_insert_rowid()).await?;
+    Ok(Ok(name))
+}
+
+/// Sets a password on a name from before accounts and logs into it. The code
+/// comes from an admin who knows whose name it is, and works once.
+#[server(ClaimName, "/api")]
+pub async fn claim_name(
+    cx: Scope,
+    name: String,
+    code: String,
+    password: String,
+) -> Result<Result<String, String>, ServerFnError> {
+    use actix_web::web;
+
+    let name = name.trim().to_string();
+    let code = code.trim().to_string();
+    if let Err(e) = check_password(&password) {
+        return Ok(Err(e));
+    }
+
+    let req = request(cx)?;
+    let db = db(&req)?;
+    let hash = web::block(move || hash_password(&password))
+        .await
+        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))??;
+    let expiry = format!("-{} days", CLAIM_DAYS);
+    // Only one claim can set the password, even if two race.
+    let claimed = sqlx::query!(
+        "
+    UPDATE player SET password_hash = ?
+    WHERE
+        name = ?
+        AND password_hash IS NULL
+        AND id IN (
+            SELECT player_id FROM name_claim WHERE code = ? AND created_at > datetime('now', ?)
+        )
+    ",
+        hash,
+        name,
+        code,
+        expiry
+    )
+    .execute(&db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    if claimed.rows_affected() == 0 {
+        return Ok(Err("wrong name or claim code".into()));
+    }
+
+    let player = sqlx::query!("SELECT id AS id FROM player WHERE name = ?", name)
+        .fetch_one(&db)
+        .await
+        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    let player_id = player.id.ok_or(ServerFnError::ServerError(
+        "failed to fetch player id".into(),
+    ))?;
+    sqlx::query!("DELETE FROM name_claim WHERE player_id = ?", player_id)
+        .execute(&db)
+        .await
+        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    log_in(cx, &db, player_id).await?;
+    Ok(Ok(name))
+}
+
+#[server(Login, "/api")]
+pub async fn login(
+    cx: Scope,
+    name: String,
+    password: String,
+) -> Result<Result<String, String>, ServerFnError> {
+    use actix_web::web;
+
+    let req = request(cx)?;
+    let db = db(&req)?;
+    let name = name.trim().to_string();
+    let player = sqlx::query!(
+        "SELECT id AS id, password_hash FROM player WHERE name = ?",
+        name
+    )
+    .fetch_optional(&db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+
+    // Unclaimed names have no password and can't be logged into. They, and
+    // names nobody has, are still checked against a hash so the response
+    // time doesn't tell which names exist.
+    let (player_id, hash) = match player.map(|player| (player.id, player.password_hash)) {
+        Some((player_id, Some(hash))) => (player_id, hash),
+        _ => (None, DUMMY_HASH.to_string()),
+    };
+    // Checking the password is as slow as hashing it.
+    let verified = web::block(move || verify_password(&password, &hash))
+        .await
+        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    let Some(player_id) = player_id.filter(|_| verified) else {
+        return Ok(Err("wrong name or password".into()));
+    };
+    log_in(cx, &db, player_id).await?;
+    Ok(Ok(name))
+}
+
+#[server(Logout, "/api")]
+pub async fn logout(cx: Scope) -> Result<(), ServerFnError> {
+    let req = request(cx)?;
+    if let Some(token) = req.cookie(SESSION_COOKIE) {
+        let token = token.value().to_owned();
+        sqlx::query!("DELETE FROM login_session WHERE token = ?", token)
+            .execute(&db(&req)?)
+            .await
+            .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    }
+    set_session_cookie(cx, "", 0)
+}
+
+/// The logged-in player's mouse bindings, if they've saved any.
+#[server(GetMouseBindings, "/api")]
+pub async fn get_mouse_bindings(cx: Scope) -> Result<Option<MouseBindings>, ServerFnError> {
+    let req = request(cx)?;
+    let Some(player) = current_player(&req).await? else {
+        return Ok(None);
+    };
+    let row = sqlx::query!("SELECT mouse_bindings FROM player WHERE id = ?", player.id)
+        .fetch_one(&db(&req)?)
+        .await
+        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    Ok(row
+        .mouse_bindings
+        .and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+/// Keeps `bindings` for the logged-in player. Anonymous players only have
+/// them in their browser.
+#[server(SaveMouseBindings, "/api")]
+pub async fn save_mouse_bindings(cx: Scope, bindings: MouseBindings) -> Result<(), ServerFnError> {
+    let req = request(cx)?;
+    let Some(player) = current_player(&req).await? else {
+        return Ok(());
+    };
+    let json = serde_json::to_string(&bindings)
+        .map_err(|msg| ServerFnError::Serialization(msg.to_string()))?;
+    sqlx::query!(
+        "UPDATE player SET mouse_bindings = ? WHERE id = ?",
+        json,
+        player.id
+    )
+    .execute(&db(&req)?)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    Ok(())
+}
+
+/// Logs in or registers. Scores are only saved for logged-in players.
+#[component]
+pub fn AccountPanel(cx: Scope) -> impl IntoView {
+    let (player, set_player) = create_signal::<Option<String>>(cx, None);
+    let (name, set_name) = create_signal(cx, String::new());
+    let (password, set_password) = create_signal(cx, String::new());
+    // Shown once the player says they have a claim code for an old name.
+    let (claim_code, set_claim_code) = create_signal::<Option<String>>(cx, None);
+    let (error, set_error) = create_signal::<Option<String>>(cx, None);
+    let updater = use_context::<GameUpdater>(cx);
+
+    // Players get the mouse bindings they saved once they're logged in.
+    let fetch_bindings = move || {
+        let Some(updater) = updater else {
+            return;
+        };
+        spawn_local(async move {
+            if let Ok(Some(bindings)) = get_mouse_bindings(cx).await {
+                updater.set_bindings.set(bindings);
+            }
+        })
+    };
+
+    spawn_local(async move {
+        if let Ok(name) = current_player_name(cx).await {
+            if name.is_some() {
+                fetch_bindings();
+            }
+            set_player(name);
+        }
+    });
+
+    let logged_in = move |result: Result<Result<String, String>, ServerFnError>| match result {
+        Ok(Ok(name)) => {
+            set_player(Some(name));
+            set_password(String::new());
+            set_claim_code(None);
+            set_error(None);
+            fetch_bindings();
+        }
+        Ok(Err(e)) => set_error(Some(e)),
+        Err(e) => set_error(Some(e.to_string())),
+    };
+    let log_in = move |_| {
+        spawn_local(async move {
+            logged_in(login(cx, name.get_untracked(), password.get_untracked()).await);
+        })
+    };
+    let sign_up = move |_| {
+        spawn_local(async move {
+            logged_in(register(cx, name.get_untracked(), password.get_untracked()).await);
+        })
+    };
+    let claim = move |_| {
+        spawn_local(async move {
+            let code = claim_code.get_untracked().unwrap_or_default();
+            logged_in(claim_name(cx, name.get_untracked(), code, password.get_untracked()).await);
+        })
+    };
+    let log_out = move |_| {
+        spawn_local(async move {
+            match logout(cx).await {
+                Ok(()) => set_player(None),
+                Err(e) => set_error(Some(e.to_string())),
+            }
+        })
+    };
+
+    view! { cx,
+        <div class="Account">
+            {move || match player() {
+                Some(player) => view! { cx,
+                    <>
+                        <span>{format!("Playing as {} ", player)}</span>
+                        <button on:click=log_out>{"Log out"}</button>
+                    </>
+                }
+                .into_view(cx),
+                None => view! { cx,
+                    <>
+                        <input
+                            type="text"
+                            placeholder="Name"
+                            prop:value=name
+                            on:input=move |ev| set_name(event_target_value(&ev))
+                        />
+                        <input
+                            type="password"
+                            placeholder="Password"
+                            prop:value=password
+                            on:input=move |ev| set_password(event_target_value(&ev))
+                        />
+                        <button on:click=log_in>{"Log in"}</button>
+                        <button on:click=sign_up>{"Register"}</button>
+                        {move || match claim_code() {
+                            Some(code) => view! { cx,
+                                <>
+                                    <input
+                                        type="text"
+                                        placeholder="Claim code"
+                                        prop:value=code
+                                        on:input=move |ev| set_claim_code(Some(event_target_value(&ev)))
+                                    />
+                                    <button on:click=claim>{"Claim"}</button>
+                                </>
+                            }
+                            .into_view(cx),
+                            None => view! { cx,
+                                <button
+                                    title="Names from before accounts are claimed with a code from the site's admin"
+                                    on:click=move |_| set_claim_code(Some(String::new()))
+                                >
+                                    {"Claim an old name"}
+                                </button>
+                            }
+                            .into_view(cx),
+                        }}
+                    </>
+                }
+                .into_view(cx),
+            }}
+            {move || error().map(|e| view! { cx, <p class="error">{e}</p> })}
+        </div>
+    }
+}
@@ -0,0 +1,141 @@
+use crate::minesweeper::{GameState, Minesweeper, Replay};
+use crate::ui::components::replay::ReplayPlayer;
+use crate::ui::shared::GameUpdater;
+use leptos::*;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+/// What a loaded file turned out to hold.
+enum BoardFile {
+    Board(Minesweeper),
+    /// A RAW video with moves in it, to be watched rather than played.
+    Video(Replay),
+}
+
+/// Loads `.mbf` layouts and RAW videos from other Minesweeper programs, and
+/// downloads the current game as either. Videos play back here, and their
+/// board can then be played.
+#[component]
+pub fn BoardFiles(cx: Scope) -> impl IntoView {
+    let updater: GameUpdater = use_context(cx).unwrap();
+    let (error, set_error) = create_signal::<Option<String>>(cx, None);
+    let (video, set_video) = create_signal::<Option<Replay>>(cx, None);
+
+    let open_file = move |ev| {
+        let input = event_target::<web_sys::HtmlInputElement>(&ev);
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        // Lets the same file be picked again.
+        input.set_value("");
+        let Ok(reader) = web_sys::FileReader::new() else {
+            return;
+        };
+
+        let name = file.name();
+        let loaded = reader.clone();
+        let onload = Closure::once_into_js(move || {
+            let Ok(result) = loaded.result() else {
+                return;
+            };
+            let bytes = js_sys::Uint8Array::new(&result).to_vec();
+            match load_board(&name, &bytes) {
+                Ok(BoardFile::Board(game)) => {
+                    updater.load_game(game, 0);
+                    set_video(None);
+                    set_error(None);
+                }
+                Ok(BoardFile::Video(replay)) => {
+                    set_video(Some(replay));
+                    set_error(None);
+                }
+                Err(e) => set_error(Some(e)),
+            }
+        });
+        reader.set_onload(Some(onload.unchecked_ref()));
+        _ = reader.read_as_array_buffer(&file);
+    };
+
+    let export_mbf = move |_| match updater.game.with_untracked(|g| g.to_mbf()) {
+        Ok(bytes) => {
+            _ = download("board.mbf", &bytes, "application/octet-stream");
+            set_error(None);
+        }
+        Err(e) => set_error(Some(e.to_string())),
+    };
+
+    let export_raw = move |_| {
+        if updater.game.with_untracked(|g| g.state) == GameState::Unstarted {
+            set_error(Some("nothing has been played yet".into()));
+            return;
+        }
+        let raw = updater.game.with_untracked(|g| g.replay().to_raw());
+        _ = download("game.rawvf", raw.as_bytes(), "text/plain");
+        set_error(None);
+    };
+
+    view! { cx,
+        <div class="BoardFiles">
+            <label>
+                {"Load .mbf / RAW: "}
+                <input type="file" accept=".mbf,.rawvf,.raw,.txt" on:change=open_file />
+            </label>
+            <div>
+                <button on:click=export_mbf>{"Download .mbf"}</button>
+                <button on:click=export_raw>{"Download RAW"}</button>
+            </div>
+            {move || error().map(|e| view! { cx, <p class="error">{e}</p> })}
+            {move || video().map(|replay| {
+                let layout = Minesweeper::from_layout(replay.height, replay.width, &replay.mines);
+                let play_board = move |_| {
+                    updater.load_game(layout.clone(), 0);
+                    set_video(None);
+                };
+                view! { cx,
+                    <div class="BoardVideo">
+                        <ReplayPlayer replay />
+                        <div>
+                            <button on:click=play_board>{"Play this board"}</button>
+                            <button on:click=move |_| set_video(None)>{"Close"}</button>
+                        </div>
+                    </div>
+                }
+            })}
+        </div>
+    }
+}
+
+/// `.mbf` files go by their extension; anything else is read as a RAW video.
+/// A video without moves is just a board.
+fn load_board(name: &str, bytes: &[u8]) -> Result<BoardFile, String> {
+    if name.to_lowercase().ends_with(".mbf") {
+        return Minesweeper::from_mbf(bytes)
+            .map(BoardFile::Board)
+            .map_err(|e| e.to_string());
+    }
+    let raw = std::str::from_utf8(bytes).map_err(|e| e.to_string())?;
+    let replay = Replay::from_raw(raw).map_err(|e| e.to_string())?;
+    if replay.events.is_empty() {
+        return Ok(BoardFile::Board(Minesweeper::from_layout(
+            replay.height,
+            replay.width,
+            &replay.mines,
+        )));
+    }
+    Ok(BoardFile::Video(replay))
+}
+
+fn download(name: &str, bytes: &[u8], mime: &str) -> Result<(), JsValue> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_(mime);
+    let blob = web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+    let link = document()
+        .create_element("a")?
+        .unchecked_into::<web_sys::HtmlAnchorElement>();
+    link.set_href(&url);
+    link.set_download(name);
+    link.click();
+    web_sys::Url::revoke_object_url(&url)
+}
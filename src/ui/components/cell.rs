@@ -1,6 +1,14 @@
-use crate::minesweeper::Pos;
+use crate::minesweeper::{Action, Pos};
 use crate::ui::shared::{GameUpdater, MouseButtons};
-use leptos::{ev::MouseEvent, *};
+use leptos::leptos_dom::helpers::TimeoutHandle;
+use leptos::{
+    ev::{MouseEvent, TouchEvent},
+    *,
+};
+use std::time::Duration;
+
+/// How long a touch is held to flag.
+const LONG_PRESS: Duration = Duration::from_millis(400);
 
 #[component]
 pub fn Cell(
@@ -10,53 +18,142 @@ pub fn Cell(
     set_active_pos: WriteSignal<Vec<Pos>>,
     mouse_down: ReadSignal<MouseButtons>,
     set_mouse_down: WriteSignal<MouseButtons>,
+    #[prop(optional)] readonly: bool,
 ) -> impl IntoView {
-    let GameUpdater { game, set_game, .. } = use_context(cx).unwrap();
+    let updater: GameUpdater = use_context(cx).unwrap();
+    let GameUpdater {
+        game,
+        hint,
+        cursor,
+        set_cursor,
+        flag_mode,
+        bindings,
+        ..
+    } = updater;
 
     let cell = move || game.with(|g| g.get_cell(pos));
     let active = move || active_pos.with(|ap| ap.contains(&pos) && !cell().kind.is_flagged());
+    let hinted = move || hint().map(|hint| hint.pos) == Some(pos) && !cell().kind.is_open();
+    let under_cursor = move || !readonly && cursor() == Some(pos);
+
+    // The cell under the keyboard cursor has focus, so screen readers follow
+    // the cursor. Until there is one, tabbing into the board lands top left.
+    let node_ref = create_node_ref::<html::Div>(cx);
+    let tabindex = move || {
+        let first = cursor().is_none() && pos == Pos { row: 0, col: 0 };
+        match !readonly && (under_cursor() || first) {
+            true => 0,
+            false => -1,
+        }
+    };
+    create_effect(cx, move |_| {
+        if under_cursor() {
+            if let Some(element) = node_ref.get() {
+                _ = element.focus();
+            }
+        }
+    });
+
+    let mouse_action = move |buttons| {
+        bindings
+            .get_untracked()
+            .action(buttons, pos, cell().kind.is_open())
+    };
+    // Shows which cells `buttons` would open if released here.
+    let press = move |buttons| match mouse_action(buttons) {
+        Some(Action::Open(_)) => set_active_pos(vec![pos]),
+        Some(Action::Chord(_)) => set_active_pos(game.with(|g| g.chorded_cells(pos))),
+        _ => (),
+    };
 
     let handle_mouse_down = move |e: MouseEvent| {
-        if game.with(|g| g.state.is_over()) {
+        if readonly || game.with(|g| g.state.is_over()) {
             e.prevent_default();
         }
+        if readonly {
+            return;
+        }
 
         let buttons = MouseButtons::from_buttons(e.buttons());
+        // Middle clicks would start the browser's autoscroll.
+        if buttons == MouseButtons::Middle {
+            e.prevent_default();
+        }
         set_mouse_down(buttons);
-        match buttons {
-            MouseButtons::LClick => set_active_pos(vec![pos]),
-            MouseButtons::LRClick => set_active_pos(game.with(|g| g.chorded_cells(pos))),
-            _ => (),
-        };
+        press(buttons);
     };
 
     let send_mouse_action = move |_| {
-        match mouse_down.get() {
-            MouseButtons::LClick => set_game.update(|game| game.open_cell(pos)),
-            MouseButtons::RClick => set_game.update(|game| game.flag_cell(pos)),
-            MouseButtons::LRClick => set_game.update(|game| game.chorded_open(pos)),
-            _ => (),
-        };
+        if let Some(action) = mouse_action(mouse_down.get()) {
+            updater.act(cx, action);
+        }
         set_active_pos(Vec::new());
         set_mouse_down(MouseButtons::None);
     };
 
-    let handle_mouse_enter = move |_| match mouse_down.get() {
-        MouseButtons::None => (),
-        MouseButtons::LClick => set_active_pos(vec![pos]),
-        MouseButtons::LRClick => set_active_pos(game.with(|g| g.chorded_cells(pos))),
-        _ => (),
-    };
+    let handle_mouse_enter = move |_| press(mouse_down.get());
 
     let handle_mouse_leave = move |_| {
         set_active_pos(vec![]);
     };
 
+    // Touches: a tap opens, or flags in flag mode, and chords on an open
+    // number. Holding flags. A touch that moves is scrolling the board.
+    let long_press = store_value::<Option<TimeoutHandle>>(cx, None);
+    let cancel_long_press = move || {
+        long_press.update_value(|press| {
+            if let Some(press) = press.take() {
+                press.clear();
+            }
+        })
+    };
+
+    let handle_touch_start = move |_| {
+        if readonly || game.with(|g| g.state.is_over()) {
+            return;
+        }
+        set_active_pos(vec![pos]);
+        let press = set_timeout_with_handle(
+            move || {
+                long_press.set_value(None);
+                set_active_pos(Vec::new());
+                updater.act(cx, Action::Flag(pos));
+            },
+            LONG_PRESS,
+        );
+        long_press.set_value(press.ok());
+    };
+
+    let handle_touch_end = move |e: TouchEvent| {
+        // The browser would follow up with mouse events for the same tap.
+        e.prevent_default();
+        set_active_pos(Vec::new());
+        let Some(press) = long_press.get_value() else {
+            return;
+        };
+        press.clear();
+        long_press.set_value(None);
+
+        let action = match cell().kind.is_open() {
+            true => Action::Chord(pos),
+            false if flag_mode.get_untracked() => Action::Flag(pos),
+            false => Action::Open(pos),
+        };
+        updater.act(cx, action);
+    };
+
+    let handle_touch_move = move |_| {
+        cancel_long_press();
+        set_active_pos(Vec::new());
+    };
+
     let class = move || {
         format!(
-            "Cell {} {}",
+            "Cell {} {} {} {}",
             cell().class,
-            if active() { "active" } else { "" }
+            if active() { "active" } else { "" },
+            if hinted() { "hint" } else { "" },
+            if under_cursor() { "cursor" } else { "" }
         )
     };
     let style = format!(
@@ -69,10 +166,28 @@ pub fn Cell(
         <div
             class=class
             style=style
+            node_ref=node_ref
+            role="gridcell"
+            aria-label=move || cell().label
+            tabindex=tabindex
+            on:focus=move |_| {
+                // Clicking focuses the cell too, but only tabbing to it moves
+                // the cursor.
+                let from_keyboard = node_ref
+                    .get()
+                    .map_or(false, |element| element.matches(":focus-visible").unwrap_or(false));
+                if !readonly && !under_cursor() && from_keyboard {
+                    set_cursor(Some(pos));
+                }
+            }
             on:mousedown=handle_mouse_down
             on:mouseup=send_mouse_action
             on:mouseenter=handle_mouse_enter
             on:mouseleave=handle_mouse_leave
+            on:touchstart=handle_touch_start
+            on:touchend=handle_touch_end
+            on:touchmove=handle_touch_move
+            on:touchcancel=handle_touch_move
             on:contextmenu=move |e| e.prevent_default()
         >
             {move || cell().icon}
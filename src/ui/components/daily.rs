@@ -0,0 +1,227 @@
+use crate::minesweeper::{FirstClickPolicy, Hint, Minesweeper, Pos, Setting, SETTINGS};
+use crate::ui::components::game::Score;
+use crate::ui::components::session::SessionStart;
+use crate::ui::shared::{format_time, GameUpdater};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+/// Every daily challenge is played on this board.
+const DAILY_SETTING: Setting = Setting {
+    first_click: FirstClickPolicy::SafeOpening,
+    ..SETTINGS[1]
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyStart {
+    pub date: String,
+    /// The cell everyone can safely start from.
+    pub start: Pos,
+    pub session: SessionStart,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyLeaderboard {
+    pub date: String,
+    pub scores: Vec<Score>,
+}
+
+/// Where the day's board is kept clear, its center.
+fn daily_start() -> Pos {
+    Pos {
+        row: DAILY_SETTING.height / 2,
+        col: DAILY_SETTING.width / 2,
+    }
+}
+
+/// Deals today's board in a ranked session. Each player gets one attempt a
+/// day, used up as soon as the board is dealt.
+#[server(NewDailySession, "/api", "Cbor")]
+pub async fn new_daily_session(
+    cx: Scope,
+    question_marks: bool,
+) -> Result<DailyStart, ServerFnError> {
+    use crate::ui::components::account::current_player;
+    use crate::ui::components::session::start_session;
+    use crate::AppState;
+    use actix_web::{web, HttpRequest};
+
+    let req =
+        use_context::<HttpRequest>(cx).ok_or(ServerFnError::ServerError("no request".into()))?;
+    let player = current_player(&req).await?.ok_or(ServerFnError::Args(
+        "log in to play the daily challenge".into(),
+    ))?;
+
+    let app_state = req
+        .app_data::<web::Data<AppState>>()
+        .ok_or(ServerFnError::ServerError("no app state".into()))?;
+    let db = &app_state.db_pool;
+
+    let date = sqlx::query!(r#"SELECT date('now') AS "date!: String""#)
+        .fetch_one(db)
+        .await
+        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?
+        .date;
+
+    // The first player of the day picks the seed for everyone.
+    let seed = rand::random::<i64>();
+    sqlx::query!(
+        "INSERT OR IGNORE INTO daily_challenge (date, seed) VALUES (?, ?)",
+        date,
+        seed
+    )
+    .execute(db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    let seed = sqlx::query!("SELECT seed FROM daily_challenge WHERE date = ?", date)
+        .fetch_one(db)
+        .await
+        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?
+        .seed;
+
+    let attempt = sqlx::query!(
+        "INSERT OR IGNORE INTO daily_attempt (player_id, date) VALUES (?, ?)",
+        player.id,
+        date
+    )
+    .execute(db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    if attempt.rows_affected() == 0 {
+        return Err(ServerFnError::Args(
+            "you've already played today's challenge".into(),
+        ));
+    }
+
+    let start = daily_start();
+    let setting = Setting {
+        question_marks,
+        ..DAILY_SETTING
+    };
+    let game = Minesweeper::dealt(setting, seed as u64, start);
+    let view = game.player_view();
+    let id = start_session(cx, game, setting, Some(date.clone()))?;
+
+    Ok(DailyStart {
+        date,
+        start,
+        session: SessionStart { id, view },
+    })
+}
+
+/// Today's challenge times, fastest first.
+#[server(GetDailyLeaderboard, "/api")]
+pub async fn get_daily_leaderboard(cx: Scope) -> Result<DailyLeaderboard, ServerFnError> {
+    use crate::AppState;
+    use actix_web::{web, HttpRequest};
+
+    let req =
+        use_context::<HttpRequest>(cx).ok_or(ServerFnError::ServerError("no request".into()))?;
+
+    let app_state = req
+        .app_data::<web::Data<AppState>>()
+        .ok_or(ServerFnError::ServerError("no app state".into()))?;
+    let db = &app_state.db_pool;
+
+    let date = sqlx::query!(r#"SELECT date('now') AS "date!: String""#)
+        .fetch_one(db)
+        .await
+        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?
+        .date;
+    // Replays show the board, so they're kept back until the day is over.
+    let scores = sqlx::query_as!(
+        Score,
+        r#"
+        SELECT
+            RANK() OVER (ORDER BY s.time_ms) AS "rank!: i64",
+            s.id AS "id!",
+            p.name AS "name!",
+            s.time_ms AS "time!",
+            s.replay IS NOT NULL AND s.challenge_date < date('now') AS "has_replay!: bool"
+        FROM
+            score AS s
+        INNER JOIN player AS p ON p.id = s.player_id
+        WHERE
+            s.challenge_date = ?
+        ORDER BY
+            s.time_ms, s.id
+        "#,
+        date
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+
+    Ok(DailyLeaderboard { date, scores })
+}
+
+/// Starts today's challenge and shows how everyone did on it.
+#[component]
+pub fn DailyChallenge(cx: Scope, scores_saved: ReadSignal<usize>) -> impl IntoView {
+    let updater = use_context::<GameUpdater>(cx).unwrap();
+    let (error, set_error) = create_signal::<Option<String>>(cx, None);
+    let leaderboard = create_resource(cx, scores_saved, move |_| get_daily_leaderboard(cx));
+
+    let play = move |_| {
+        spawn_local(async move {
+            let question_marks = updater.setting.get_untracked().question_marks;
+            match new_daily_session(cx, question_marks).await {
+                Ok(daily) => {
+                    set_error(None);
+                    updater.set_setting.set(Setting {
+                        question_marks,
+                        ..DAILY_SETTING
+                    });
+                    updater.set_time.set(0);
+                    updater
+                        .set_game
+                        .set(Minesweeper::from_player_view(daily.session.view));
+                    updater.set_session.set(Some(daily.session.id));
+                    updater.set_hint.set(Some(Hint::safe(daily.start)));
+                }
+                Err(e) => set_error(Some(e.to_string())),
+            }
+        })
+    };
+
+    view! { cx,
+        <div class="Daily">
+            <button on:click=play>{"Play today's challenge"}</button>
+            {move || error().map(|e| view! { cx, <p class="error">{e}</p> })}
+            <Suspense fallback=move || view! { cx, <p>{"Loading scores..."}</p> }>
+                {move || {
+                    leaderboard.read(cx).and_then(Result::ok).map(|daily| view! { cx,
+                        <>
+                            <h4>{format!("Daily challenge {}", daily.date)}</h4>
+                            <table>
+                                {daily
+                                    .scores
+                                    .into_iter()
+                                    .map(|score| view! { cx,
+                                        <tr>
+                                            <td style="width: 2em; text-align: right">
+                                                {format!("{}.", score.rank)}
+                                            </td>
+                                            <td style="width: 8em">
+                                                <a href=format!("/stats/{}", score.name)>{score.name}</a>
+                                            </td>
+                                            <td style="width: 4em; text-align: right">
+                                                {format_time(score.time)}
+                                            </td>
+                                            <td style="width: 1em">
+                                                {score.has_replay.then(|| view! { cx,
+                                                    <a href=format!("/replay/{}", score.id) title="Watch replay">
+                                                        {"▶"}
+                                                    </a>
+                                                })}
+                                            </td>
+                                        </tr>
+                                    })
+                                    .collect::<Vec<_>>()}
+                            </table>
+                        </>
+                    })
+                }}
+            </Suspense>
+        </div>
+    }
+}
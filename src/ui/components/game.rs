@@ -1,21 +1,50 @@
-use std::collections::HashMap;
-
-use crate::minesweeper::Difficulty;
-use crate::minesweeper::{GameState, Minesweeper, Pos, SETTINGS};
+use crate::minesweeper::{
+    Action, GameState, Hint, Metrics, Minesweeper, Pos, ReplayError, SETTINGS,
+};
+use crate::minesweeper::{Difficulty, FirstClickPolicy};
 use leptos::leptos_dom::helpers::IntervalHandle;
 use leptos::*;
 use leptos_meta::Title;
-use wasm_bindgen::JsValue;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 
+use crate::ui::components::account::*;
+use crate::ui::components::board_files::*;
 use crate::ui::components::cell::*;
+use crate::ui::components::daily::*;
 use crate::ui::components::leaderboards::*;
 use crate::ui::components::scoreboard::*;
+use crate::ui::components::session::submit_session;
 use crate::ui::components::settings::*;
+use crate::ui::components::snapshot::*;
+use crate::ui::components::stats::record_game;
 
-use crate::ui::shared::{GameUpdater, MouseButtons, CELL_SIZE};
+use crate::ui::shared::{GameUpdater, MouseBindings, MouseButtons, CELL_SIZE};
+use crate::ui::storage::{load_bindings, load_game, save_bindings, save_game};
 
 const TIMER_MAX: u16 = 999;
 
+fn key_target(e: &ev::KeyboardEvent) -> Option<web_sys::HtmlElement> {
+    e.target()
+        .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+}
+
+/// Whether `e` is typing into a field rather than playing.
+fn is_typing(e: &ev::KeyboardEvent) -> bool {
+    key_target(e).map_or(false, |element| {
+        element.is_content_editable()
+            || matches!(element.tag_name().as_str(), "INPUT" | "SELECT" | "TEXTAREA")
+    })
+}
+
+/// Whether `e` went to the board rather than a control elsewhere on the page.
+/// Buttons and links keep space and enter for themselves.
+fn is_on_board(e: &ev::KeyboardEvent) -> bool {
+    key_target(e).map_or(true, |element| {
+        element.tag_name() == "BODY" || matches!(element.closest(".Board"), Ok(Some(_)))
+    })
+}
+
 #[component]
 pub fn Game(cx: Scope) -> impl IntoView {
     let (game, set_game) = create_signal(cx, Minesweeper::new(9, 9, 10));
@@ -23,33 +52,145 @@ pub fn Game(cx: Scope) -> impl IntoView {
     let (mouse_down, set_mouse_down) = create_signal::<MouseButtons>(cx, MouseButtons::None);
     let (setting, set_setting) = create_signal(cx, SETTINGS[0]);
     let (time, set_time) = create_signal::<u16>(cx, 0);
-    let (scores, set_scores) = create_signal::<LeaderboardScores>(cx, LeaderboardScores::default());
+    // Bumped when a score is saved so the leaderboards fetch again.
+    let (scores_saved, set_scores_saved) = create_signal(cx, 0usize);
+    let (hint, set_hint) = create_signal::<Option<Hint>>(cx, None);
+    let (cursor, set_cursor) = create_signal::<Option<Pos>>(cx, None);
+    let (flag_mode, set_flag_mode) = create_signal(cx, false);
+    let (bindings, set_bindings) = create_signal(cx, MouseBindings::default());
+    // Read out by screen readers when it changes.
+    let (announcement, set_announcement) = create_signal(cx, String::new());
+    let (ranked, set_ranked) = create_signal(cx, false);
+    let (session, set_session) = create_signal::<Option<String>>(cx, None);
+    let (metrics, set_metrics) = create_signal::<Option<Metrics>>(cx, None);
+    // A won session that's waiting for a login to be saved.
+    let (unsaved, set_unsaved) = create_signal::<Option<String>>(cx, None);
 
     let game_state = store_value(cx, game.with(|g| g.state));
     let interval = store_value::<Option<Result<IntervalHandle, JsValue>>>(cx, None);
 
-    let board_pos = move || game.with(|g| g.board.iter_pos().collect::<Vec<Pos>>());
-    provide_context(
-        cx,
-        GameUpdater {
-            game,
-            set_game,
-            setting,
-            set_setting,
-            time,
-        },
-    );
-
-    let fetch_and_set_score = move || {
+    let board_rows = move || game.with(|g| (0..g.board.height).collect::<Vec<u8>>());
+    let row_pos = move |row| {
+        game.with(|g| {
+            (0..g.board.width)
+                .map(|col| Pos { row, col })
+                .collect::<Vec<Pos>>()
+        })
+    };
+    let updater = GameUpdater {
+        game,
+        set_game,
+        setting,
+        set_setting,
+        time,
+        set_time,
+        hint,
+        set_hint,
+        cursor,
+        set_cursor,
+        flag_mode,
+        set_flag_mode,
+        bindings,
+        set_bindings,
+        ranked,
+        set_ranked,
+        session,
+        set_session,
+    };
+    provide_context(cx, updater);
+
+    let on_key = move |e: ev::KeyboardEvent| {
+        // Keys typed into the page's fields aren't for the board.
+        if is_typing(&e) {
+            return;
+        }
+        let plain = !(e.ctrl_key() || e.alt_key() || e.meta_key());
+        let mut handled = true;
+        match e.key().as_str() {
+            "ArrowUp" => updater.move_cursor(-1, 0),
+            "ArrowDown" => updater.move_cursor(1, 0),
+            "ArrowLeft" => updater.move_cursor(0, -1),
+            "ArrowRight" => updater.move_cursor(0, 1),
+            "k" if plain => updater.move_cursor(-1, 0),
+            "j" if plain => updater.move_cursor(1, 0),
+            "h" if plain => updater.move_cursor(0, -1),
+            "l" if plain => updater.move_cursor(0, 1),
+            " " | "Enter" if is_on_board(&e) => updater.act_at_cursor(cx, Action::Open),
+            "f" if plain => updater.act_at_cursor(cx, Action::Flag),
+            "d" if plain => updater.act_at_cursor(cx, Action::Chord),
+            "n" if plain => updater.new_game(cx),
+            "F2" => updater.new_game(cx),
+            "?" => updater.request_hint(),
+            "z" | "Z" if e.ctrl_key() => updater.undo(),
+            "y" | "Y" if e.ctrl_key() => updater.redo(),
+            _ => handled = false,
+        }
+        // Arrows and space would scroll the page otherwise.
+        if handled {
+            e.prevent_default();
+        }
+    };
+    // Added by hand rather than with `window_event_listener` so it can be
+    // taken off again: it would otherwise outlive this page's signals.
+    create_effect(cx, move |_| {
+        let listener = Closure::<dyn Fn(ev::KeyboardEvent)>::new(on_key).into_js_value();
+        _ = window().add_event_listener_with_callback("keydown", listener.unchecked_ref());
+        on_cleanup(cx, move || {
+            _ = window().remove_event_listener_with_callback("keydown", listener.unchecked_ref());
+        });
+    });
+
+    let fetch_and_set_score = move || set_scores_saved.update(|saved| *saved += 1);
+
+    let submit = move |id: String| {
         spawn_local(async move {
-            let scores = get_leaderboard_scores(cx).await;
-            if let Ok(scores) = scores {
-                set_scores.set(scores);
+            match submit_session(cx, id.clone()).await {
+                Ok(Ok(metrics)) => {
+                    set_metrics.set(Some(metrics));
+                    set_unsaved.set(None);
+                }
+                Ok(Err(ScoreRejection::LoggedOut)) => set_unsaved.set(Some(id)),
+                Ok(Err(rejection)) => log::warn!("score rejected: {}", rejection),
+                Err(e) => log::warn!("score not saved: {}", e),
             }
+
+            fetch_and_set_score();
         })
     };
 
-    fetch_and_set_score();
+    let start_timer = move || {
+        let int = set_interval_with_handle(
+            move || set_time.update(|time| *time += 1),
+            std::time::Duration::from_secs(1),
+        );
+        interval.update_value(|i| *i = Some(int));
+    };
+
+    // Effects only run in the browser, which is where the saved game and
+    // bindings are.
+    create_effect(cx, move |_| set_bindings.set(load_bindings()));
+    create_effect(cx, move |_| save_bindings(bindings.get()));
+
+    create_effect(cx, move |_| {
+        let Some(restored) = load_game() else {
+            return;
+        };
+        let state = restored.game.state;
+        set_time.set(restored.time);
+        set_setting.set(restored.setting);
+        set_ranked.set(restored.session.is_some());
+        set_session.set(restored.session);
+        game_state.update_value(|gs| *gs = state);
+        set_game.set(restored.game);
+        if state == GameState::Playing {
+            start_timer();
+        }
+    });
+
+    create_effect(cx, move |_| {
+        let (setting, session) = (setting.get(), session.get());
+        game.with(|g| save_game(setting, g, time.get_untracked(), session));
+    });
 
     create_effect(cx, move |_| {
         let state = game.with(|g| g.state);
@@ -61,34 +202,68 @@ pub fn Game(cx: Scope) -> impl IntoView {
         }
 
         game_state.update_value(|gs| *gs = state);
+        set_hint.set(None);
+        set_unsaved.set(None);
+        set_metrics.set(match session.get_untracked() {
+            Some(_) => None,
+            None => game.with_untracked(|g| g.metrics()),
+        });
         if game_state() == GameState::Playing {
-            let int = set_interval_with_handle(
-                move || set_time.update(|time| *time += 1),
-                std::time::Duration::from_secs(1),
-            );
-            interval.update_value(|i| *i = Some(int));
+            start_timer();
         } else {
             if interval().is_some() {
                 interval().unwrap().unwrap().clear();
             }
             match game_state() {
                 GameState::Unstarted => set_time.set(0),
-                GameState::Win if setting.get_untracked().difficulty != Difficulty::Custom => {
-                    set_playername();
-                    spawn_local(async move {
-                        _ = save_player_score(
-                            cx,
-                            time.get_untracked(),
-                            setting.get_untracked().difficulty,
-                        )
-                        .await;
-
-                        fetch_and_set_score();
-                    });
+                GameState::Win if session.get_untracked().is_some() => {
+                    submit(session.get_untracked().unwrap());
                 }
                 _ => {}
             }
+
+            // Session games are recorded by the server. Games helped along by
+            // hints or undo stay out of the stats, like loaded ones.
+            let local = session.get_untracked().is_none() && game.with_untracked(|g| g.is_ranked());
+            if game_state().is_over() && local {
+                let (replay, clicks) = game.with_untracked(|g| (g.replay(), g.clicks));
+                spawn_local(async move {
+                    if let Err(e) = record_game(cx, replay, clicks).await {
+                        log::warn!("game not recorded: {}", e);
+                    }
+                });
+            }
+        }
+    });
+
+    // Tells screen readers how a game ended, and how much a click opened when
+    // it cascaded.
+    create_effect(cx, move |before: Option<(GameState, usize)>| {
+        let now = game.with(|g| {
+            let opened = g
+                .board
+                .iter()
+                .flatten()
+                .filter(|kind| kind.is_open())
+                .count();
+            (g.state, opened)
+        });
+        let Some((state, opened)) = before else {
+            return now;
+        };
+        match now {
+            (GameState::Win, _) if state != GameState::Win => {
+                set_announcement.set("You won!".into())
+            }
+            (GameState::Lose, _) if state != GameState::Lose => {
+                set_announcement.set("You hit a mine. Game over.".into())
+            }
+            (GameState::Playing, now_opened) if now_opened > opened + 1 => {
+                set_announcement.set(format!("{} cells opened", now_opened - opened))
+            }
+            _ => {}
         }
+        now
     });
 
     let style = move || {
@@ -106,25 +281,59 @@ pub fn Game(cx: Scope) -> impl IntoView {
 
         <div class="game">
             <Scoreboard />
-            <div class="Board" style=style>
-                <For
-                    each=board_pos
-                    key=|&pos| pos.key()
-                    view=move |cx, pos| {
-                        view! { cx,
-                            <Cell
-                                pos
-                                active_pos
-                                set_active_pos
-                                mouse_down
-                                set_mouse_down
-                            />
-                        }
+            <DealtNotice />
+            <UnrankedNotice />
+            <HintNotice />
+            <MetricsPanel metrics />
+            {move || {
+                unsaved().map(|id| {
+                    view! { cx,
+                        <p class="UnsavedScore">
+                            {"Log in to keep this win. "}
+                            <button on:click=move |_| submit(id.clone())>{"Save score"}</button>
+                        </p>
                     }
-                />
+                })
+            }}
+            <div class="BoardScroll">
+                <div class="Board" role="grid" aria-label="Minesweeper board" style=style>
+                    <For
+                        each=board_rows
+                        key=|&row| row
+                        view=move |cx, row| {
+                            view! { cx,
+                                <div class="Row" role="row">
+                                    <For
+                                        each=move || row_pos(row)
+                                        key=|&pos| pos.key()
+                                        view=move |cx, pos| {
+                                            view! { cx,
+                                                <Cell
+                                                    pos
+                                                    active_pos
+                                                    set_active_pos
+                                                    mouse_down
+                                                    set_mouse_down
+                                                />
+                                            }
+                                        }
+                                    />
+                                </div>
+                            }
+                        }
+                    />
+                </div>
+                <div class="visually-hidden" role="status" aria-live="polite">
+                    {announcement}
+                </div>
             </div>
             <SettingsPanel />
-            <Leaderboards scores />
+            <AccountPanel />
+            <SnapshotPanel />
+            <BoardFiles />
+            <a class="stats-link" href="/stats">{"My stats"}</a>
+            <DailyChallenge scores_saved />
+            <Leaderboards scores_saved />
         </div>
     }
 }
@@ -138,77 +347,98 @@ impl Difficulty {
             Difficulty::Custom => 4,
         }
     }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Difficulty::Beginner),
+            2 => Some(Difficulty::Intermediate),
+            3 => Some(Difficulty::Expert),
+            4 => Some(Difficulty::Custom),
+            _ => None,
+        }
+    }
 }
 
-#[server(SaveScore, "/api")]
-pub async fn save_player_score(
-    cx: Scope,
-    time: u16,
+/// Why a won session's score wasn't accepted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ScoreRejection {
+    InvalidReplay(ReplayError),
+    /// Nobody's logged in to save it under. The session is kept, so it can
+    /// be submitted again after logging in.
+    LoggedOut,
+}
+
+impl std::fmt::Display for ScoreRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScoreRejection::InvalidReplay(e) => write!(f, "invalid replay: {}", e),
+            ScoreRejection::LoggedOut => f.write_str("log in to save scores"),
+        }
+    }
+}
+
+/// Saves the score of `replay` under the logged-in player, timed by the
+/// replay itself. Scores on a daily challenge board carry its
+/// `challenge_date`.
+#[cfg(feature = "ssr")]
+pub(crate) async fn insert_score(
+    req: &actix_web::HttpRequest,
     difficulty: Difficulty,
+    replay: &crate::minesweeper::Replay,
+    metrics: &Metrics,
+    challenge_date: Option<&str>,
 ) -> Result<(), ServerFnError> {
+    use crate::minesweeper::Clicks;
+    use crate::ui::components::account::current_player;
     use crate::AppState;
-    use actix_web::{web, HttpRequest};
+    use actix_web::web;
+
     let difficulty_id = difficulty.id();
-    let req =
-        use_context::<HttpRequest>(cx).ok_or(ServerFnError::ServerError("no request".into()))?;
+    let first_click = replay.first_click.id();
+    let (width, height, mine_count) = (replay.width, replay.height, replay.mine_count as i64);
 
-    let playername = req
-        .cookie("playername")
-        .map(|cookie| cookie.value().to_owned())
-        .ok_or(ServerFnError::ServerError(
-            "playername cookie field not set".into(),
-        ))?;
+    let player_id = current_player(req)
+        .await?
+        .ok_or(ServerFnError::ServerError("log in to save scores".into()))?
+        .id;
 
     let app_state = req
         .app_data::<web::Data<AppState>>()
         .ok_or(ServerFnError::ServerError("no app state".into()))?;
     let db = &app_state.db_pool;
 
-    _ = sqlx::query!("INSERT OR IGNORE INTO player (name) VALUES (?)", playername)
-        .execute(db)
-        .await;
-
-    let player = sqlx::query!("SELECT id AS id FROM player WHERE name = ?", playername)
-        .fetch_one(db)
-        .await
-        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
-
-    if player.id.is_none() {
-        return Err(ServerFnError::ServerError(
-            "failed to fetch player id".into(),
-        ));
-    }
-
-    let scores = sqlx::query!(
-        "
-    SELECT COUNT(*) AS 'count'
-    FROM
-        score AS s
-        JOIN difficulty AS d ON s.difficulty_id = d.id
-    WHERE
-        s.time < ?
-        AND d.id = ?
-    ORDER BY s.time
-    ",
-        time,
-        difficulty_id
-    )
-    .fetch_one(db)
-    .await
-    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
-
-    if scores.count >= 10 {
-        return Ok(());
-    }
+    let time = replay.duration();
+    let replay = serde_json::to_string(replay)
+        .map_err(|msg| ServerFnError::Serialization(msg.to_string()))?;
+    let seconds = time / 1000;
+    let (bbbv, zini) = (metrics.bbbv as i64, metrics.zini as i64);
+    let Clicks { left, right, chord } = metrics.clicks;
+    let (left, right, chord) = (left as i64, right as i64, chord as i64);
 
     _ = sqlx::query!(
         "
-    INSERT INTO score (player_id, difficulty_id, time)
-    VALUES (?, ?, ?)
+    INSERT INTO score (
+        player_id, difficulty_id, time, time_ms, first_click, replay,
+        bbbv, zini, left_clicks, right_clicks, chord_clicks, width, height, mine_count,
+        challenge_date
+    )
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
     ",
-        player.id,
+        player_id,
         difficulty_id,
-        time
+        seconds,
+        time,
+        first_click,
+        replay,
+        bbbv,
+        zini,
+        left,
+        right,
+        chord,
+        width,
+        height,
+        mine_count,
+        challenge_date
     )
     .execute(db)
     .await
@@ -217,64 +447,130 @@ pub async fn save_player_score(
     Ok(())
 }
 
-fn set_playername() {
-    let doc = document().unchecked_into::<web_sys::HtmlDocument>();
-    if is_playname_set(&doc.cookie().unwrap()) {
-        return;
-    }
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Score {
+    /// Position among the scores the leaderboard was filtered to; tied
+    /// times share one.
+    pub rank: i64,
+    pub id: i64,
+    pub name: String,
+    /// Milliseconds from the first click to the winning one.
+    pub time: i64,
+    pub has_replay: bool,
+}
 
-    use wasm_bindgen::JsCast;
-    let name = window()
-        .prompt_with_message("Enter name to save score")
-        .unwrap()
-        .unwrap_or_default();
+/// How far back a leaderboard looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeWindow {
+    Today,
+    Week,
+    Month,
+    All,
+}
 
-    if name.is_empty() {
-        return;
+impl TimeWindow {
+    pub const ALL: [TimeWindow; 4] = [
+        TimeWindow::Today,
+        TimeWindow::Week,
+        TimeWindow::Month,
+        TimeWindow::All,
+    ];
+
+    /// SQLite `datetime('now', ...)` modifier for the window's start.
+    #[cfg(feature = "ssr")]
+    fn start_modifier(&self) -> Option<&'static str> {
+        match self {
+            TimeWindow::Today => Some("start of day"),
+            TimeWindow::Week => Some("-7 days"),
+            TimeWindow::Month => Some("-1 month"),
+            TimeWindow::All => None,
+        }
     }
-
-    _ = doc.set_cookie(format!("playername={}; SameSite=None; Secure", name).as_str());
 }
 
-fn is_playname_set(cookie: &str) -> bool {
-    for item in cookie.split(";") {
-        let mut item = item.split("=");
-        if item.next() == Some("playername") {
-            return true;
+impl std::fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeWindow::Today => write!(f, "Today"),
+            TimeWindow::Week => write!(f, "This week"),
+            TimeWindow::Month => write!(f, "This month"),
+            TimeWindow::All => write!(f, "All time"),
         }
     }
-    return false;
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Score {
-    pub id: i64,
-    pub name: String,
-    pub time: i64,
+/// The dimensions that make custom boards comparable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardSize {
+    pub width: u8,
+    pub height: u8,
+    pub mine_count: usize,
 }
 
-use serde::{Deserialize, Serialize};
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LeaderboardScores(pub HashMap<Difficulty, Vec<Score>>);
-
-impl LeaderboardScores {
-    pub fn diff_scores(&self, difficulty: &Difficulty) -> Vec<Score> {
-        // self.0[difficulty].clone()
-        self.0.get(difficulty).unwrap_or(&Vec::new()).clone()
+impl std::fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}x{}, {} mines",
+            self.width, self.height, self.mine_count
+        )
     }
 }
 
-impl Default for LeaderboardScores {
-    fn default() -> Self {
-        Self(Default::default())
-    }
+/// A custom board size that has scores, and how many.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CustomBoard {
+    pub size: BoardSize,
+    pub scores: i64,
+}
+
+/// Which scores a leaderboard shows and which page of them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardQuery {
+    pub difficulty: Difficulty,
+    /// Required for custom boards, which are only ranked against boards of
+    /// the same size.
+    pub board: Option<BoardSize>,
+    pub first_click: FirstClickPolicy,
+    pub window: TimeWindow,
+    /// Zero-based.
+    pub page: u32,
+    pub page_size: u32,
+    /// Only this player's scores, still ranked against everyone's.
+    pub player: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LeaderboardPage {
+    pub scores: Vec<Score>,
+    /// Number of scores on every page together.
+    pub total: i64,
 }
 
-#[server(GetScores, "/api")]
-pub async fn get_leaderboard_scores(cx: Scope) -> Result<LeaderboardScores, ServerFnError> {
+const MAX_PAGE_SIZE: u32 = 100;
+
+#[server(GetLeaderboard, "/api")]
+pub async fn get_leaderboard(
+    cx: Scope,
+    query: LeaderboardQuery,
+) -> Result<LeaderboardPage, ServerFnError> {
     use crate::AppState;
     use actix_web::{web, HttpRequest};
 
+    if query.page_size == 0 || query.page_size > MAX_PAGE_SIZE {
+        return Err(ServerFnError::Args(format!(
+            "page size must be 1 to {}",
+            MAX_PAGE_SIZE
+        )));
+    }
+
+    let board = match query.difficulty {
+        Difficulty::Custom => Some(query.board.ok_or(ServerFnError::Args(
+            "custom leaderboards need a board size".into(),
+        ))?),
+        _ => None,
+    };
+
     let req =
         use_context::<HttpRequest>(cx).ok_or(ServerFnError::ServerError("no request".into()))?;
 
@@ -283,34 +579,159 @@ pub async fn get_leaderboard_scores(cx: Scope) -> Result<LeaderboardScores, Serv
         .ok_or(ServerFnError::ServerError("no app state".into()))?;
     let db = &app_state.db_pool;
 
-    let mut scores = LeaderboardScores(HashMap::new());
-    for difficulty in &[
-        Difficulty::Beginner,
-        Difficulty::Intermediate,
-        Difficulty::Expert,
-    ] {
-        let diff_id = difficulty.id();
-        let diff_score = sqlx::query_as!(
-            Score,
-            r#"
-        SELECT s.id AS "id!", p.name AS "name!", s.time AS "time!"
+    let difficulty_id = query.difficulty.id();
+    let width = board.map(|b| b.width);
+    let height = board.map(|b| b.height);
+    let mine_count = board.map(|b| b.mine_count as i64);
+    let first_click = query.first_click.id();
+    let since = query.window.start_modifier();
+    let player = query.player.filter(|name| !name.is_empty());
+    // Widened so a far-off page can't overflow; the page size is capped above.
+    let offset = query.page as i64 * query.page_size as i64;
+
+    // Ranks are taken before the player filter so they stay the
+    // leaderboard's.
+    let scores = sqlx::query_as!(
+        Score,
+        r#"
+        SELECT
+            rank AS "rank!: i64",
+            id AS "id!",
+            name AS "name!",
+            time AS "time!",
+            has_replay AS "has_replay!: bool"
+        FROM (
+            SELECT
+                RANK() OVER (ORDER BY s.time_ms) AS rank,
+                s.id AS id,
+                p.name AS name,
+                s.time_ms AS time,
+                s.replay IS NOT NULL AS has_replay
+            FROM
+                score AS s
+            INNER JOIN player AS p ON p.id = s.player_id
+            WHERE
+                s.difficulty_id = ?
+                AND s.first_click = ?
+                AND s.challenge_date IS NULL
+                AND (? IS NULL OR s.inserted_at >= datetime('now', ?))
+                AND (? IS NULL OR (s.width = ? AND s.height = ? AND s.mine_count = ?))
+        )
+        WHERE
+            ? IS NULL OR name = ?
+        ORDER BY
+            rank, id
+        LIMIT ? OFFSET ?
+        "#,
+        difficulty_id,
+        first_click,
+        since,
+        since,
+        width,
+        width,
+        height,
+        mine_count,
+        player,
+        player,
+        query.page_size,
+        offset
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+
+    let total = sqlx::query!(
+        r#"
+        SELECT
+            COUNT(*) AS "count!: i64"
         FROM
             score AS s
         INNER JOIN player AS p ON p.id = s.player_id
-        INNER JOIN difficulty AS d ON d.id = s.difficulty_id
         WHERE
-            d.id = ?
+            s.difficulty_id = ?
+            AND s.first_click = ?
+            AND s.challenge_date IS NULL
+            AND (? IS NULL OR s.inserted_at >= datetime('now', ?))
+            AND (? IS NULL OR (s.width = ? AND s.height = ? AND s.mine_count = ?))
+            AND (? IS NULL OR p.name = ?)
+        "#,
+        difficulty_id,
+        first_click,
+        since,
+        since,
+        width,
+        width,
+        height,
+        mine_count,
+        player,
+        player
+    )
+    .fetch_one(db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?
+    .count;
+
+    Ok(LeaderboardPage { scores, total })
+}
+
+const CUSTOM_BOARDS: i64 = 10;
+
+/// The custom board sizes with the most scores for `first_click`, most
+/// popular first.
+#[server(GetCustomBoards, "/api")]
+pub async fn get_custom_boards(
+    cx: Scope,
+    first_click: FirstClickPolicy,
+) -> Result<Vec<CustomBoard>, ServerFnError> {
+    use crate::AppState;
+    use actix_web::{web, HttpRequest};
+
+    let req =
+        use_context::<HttpRequest>(cx).ok_or(ServerFnError::ServerError("no request".into()))?;
+
+    let app_state = req
+        .app_data::<web::Data<AppState>>()
+        .ok_or(ServerFnError::ServerError("no app state".into()))?;
+    let db = &app_state.db_pool;
+
+    let difficulty_id = Difficulty::Custom.id();
+    let first_click = first_click.id();
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            width AS "width!: u8",
+            height AS "height!: u8",
+            mine_count AS "mine_count!",
+            COUNT(*) AS "scores!: i64"
+        FROM
+            score
+        WHERE
+            difficulty_id = ?
+            AND first_click = ?
+            AND challenge_date IS NULL
+        GROUP BY
+            width, height, mine_count
         ORDER BY
-            s.time
-        LIMIT 10;
+            COUNT(*) DESC, width, height, mine_count
+        LIMIT ?
         "#,
-            diff_id
-        )
-        .fetch_all(db)
-        .await
-        .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+        difficulty_id,
+        first_click,
+        CUSTOM_BOARDS
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
 
-        scores.0.insert(*difficulty, diff_score);
-    }
-    Ok(scores)
+    Ok(rows
+        .into_iter()
+        .map(|row| CustomBoard {
+            size: BoardSize {
+                width: row.width,
+                height: row.height,
+                mine_count: row.mine_count as usize,
+            },
+            scores: row.scores,
+        })
+        .collect())
 }
@@ -1,62 +1,229 @@
-use crate::minesweeper::Difficulty;
-use crate::ui::components::game::LeaderboardScores;
+use crate::minesweeper::{Difficulty, FirstClickPolicy};
+use crate::ui::components::game::{
+    get_custom_boards, get_leaderboard, BoardSize, LeaderboardPage, LeaderboardQuery, TimeWindow,
+};
+use crate::ui::components::settings::FirstClickSelect;
+use crate::ui::shared::format_time;
 use leptos::*;
 
-#[component]
-pub fn Leaderboards(cx: Scope, scores: ReadSignal<LeaderboardScores>) -> impl IntoView {
-    view! { cx,
-        <div class="Leaderboards">
-            <Leaderboard difficulty=Difficulty::Beginner scores />
-            <Leaderboard difficulty=Difficulty::Intermediate scores />
-            <Leaderboard difficulty=Difficulty::Expert scores />
-        </div>
-    }
-}
+const PAGE_SIZE: u32 = 10;
+const DIFFICULTIES: [Difficulty; 4] = [
+    Difficulty::Beginner,
+    Difficulty::Intermediate,
+    Difficulty::Expert,
+    Difficulty::Custom,
+];
 
+/// One page of the times for a difficulty, time window and first-click
+/// policy, optionally narrowed to one player. Custom boards are ranked by
+/// size, starting with the size that has the most scores. Fetched again
+/// whenever `scores_saved` changes.
 #[component]
-fn Leaderboard(
-    cx: Scope,
-    difficulty: Difficulty,
-    scores: ReadSignal<LeaderboardScores>,
-) -> impl IntoView {
+pub fn Leaderboards(cx: Scope, scores_saved: ReadSignal<usize>) -> impl IntoView {
+    let (difficulty, set_difficulty) = create_signal(cx, Difficulty::Beginner);
+    let (window, set_window) = create_signal(cx, TimeWindow::All);
+    let (first_click, set_first_click) = create_signal(cx, FirstClickPolicy::default());
+    let (player, set_player) = create_signal(cx, String::new());
+    let (page, set_page) = create_signal(cx, 0u32);
+    let (board, set_board) = create_signal::<Option<BoardSize>>(cx, None);
+
+    let custom_boards = create_resource(
+        cx,
+        move || (first_click(), scores_saved()),
+        move |(first_click, _)| get_custom_boards(cx, first_click),
+    );
+    let board = move || {
+        board().or_else(|| {
+            custom_boards
+                .with(cx, |boards| {
+                    boards
+                        .as_ref()
+                        .ok()
+                        .and_then(|boards| boards.first().map(|b| b.size))
+                })
+                .flatten()
+        })
+    };
+
+    let query = move || LeaderboardQuery {
+        difficulty: difficulty(),
+        board: match difficulty() {
+            Difficulty::Custom => board(),
+            _ => None,
+        },
+        first_click: first_click(),
+        window: window(),
+        page: page(),
+        page_size: PAGE_SIZE,
+        player: Some(player()).filter(|name| !name.is_empty()),
+    };
+    let leaderboard = create_resource(
+        cx,
+        move || (query(), scores_saved()),
+        move |(query, _)| async move {
+            match query.difficulty == Difficulty::Custom && query.board.is_none() {
+                // No custom scores yet.
+                true => Ok(LeaderboardPage::default()),
+                false => get_leaderboard(cx, query).await,
+            }
+        },
+    );
+    let page_count = move || {
+        leaderboard.with(cx, |leaderboard| {
+            leaderboard
+                .as_ref()
+                .map_or(1, |scores| (scores.total as u32 + PAGE_SIZE - 1) / PAGE_SIZE)
+                .max(1)
+        })
+    };
+
+    let select_first_click = move |policy| {
+        set_first_click(policy);
+        set_board(None);
+        set_page(0);
+    };
 
     view! { cx,
         <div class="Leaderboard">
-            <h4>{difficulty.to_string()}</h4>
-            <table>
-                <For
-                    each=move || scores.with(|s| s.diff_scores(&difficulty))
-                    key=|score| score.time
-                    view=move |cx, score| {
-                        view! { cx,
-                            <tr>
-                                // <td style="width: 1em; text-align: left">{1}</td>
-                                <td style="width: 8em">{score.name}</td>
-                                <td style="width: 2em; text-align: right">{score.time}</td>
-                            </tr>
+            <p class="leaderboard-note">{"Only ranked games, dealt and timed by the server, are listed."}</p>
+            <div class="leaderboard-tabs">
+                {DIFFICULTIES
+                    .into_iter()
+                    .map(|d| view! { cx,
+                        <button
+                            class:selected=move || difficulty() == d
+                            on:click=move |_| {
+                                set_difficulty(d);
+                                set_page(0);
+                            }
+                        >
+                            {d.to_string()}
+                        </button>
+                    })
+                    .collect::<Vec<_>>()}
+            </div>
+            <div class="leaderboard-tabs">
+                {TimeWindow::ALL
+                    .into_iter()
+                    .map(|w| view! { cx,
+                        <button
+                            class:selected=move || window() == w
+                            on:click=move |_| {
+                                set_window(w);
+                                set_page(0);
+                            }
+                        >
+                            {w.to_string()}
+                        </button>
+                    })
+                    .collect::<Vec<_>>()}
+            </div>
+            <label class="leaderboard-policy">
+                {"First click "}
+                <FirstClickSelect selected=first_click on_select=select_first_click />
+            </label>
+            {move || (difficulty() == Difficulty::Custom).then(|| view! { cx,
+                <label class="leaderboard-policy">
+                    {"Board "}
+                    <select on:change=move |ev| {
+                        let size = event_target_value(&ev)
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|i| {
+                                custom_boards
+                                    .with(cx, |boards| {
+                                        boards.as_ref().ok().and_then(|b| b.get(i).map(|b| b.size))
+                                    })
+                                    .flatten()
+                            });
+                        set_board(size);
+                        set_page(0);
+                    }>
+                        {move || {
+                            custom_boards
+                                .read(cx)
+                                .and_then(Result::ok)
+                                .unwrap_or_default()
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, custom)| view! { cx,
+                                    <option
+                                        value=i
+                                        prop:selected={move || board() == Some(custom.size)}
+                                    >
+                                        {format!("{} ({})", custom.size, custom.scores)}
+                                    </option>
+                                })
+                                .collect::<Vec<_>>()
+                        }}
+                    </select>
+                </label>
+            })}
+            <input
+                class="leaderboard-player"
+                type="text"
+                placeholder="Player"
+                prop:value=player
+                on:change=move |ev| {
+                    set_player(event_target_value(&ev).trim().to_string());
+                    set_page(0);
+                }
+            />
+            <Suspense fallback=move || view! { cx, <p>{"Loading scores..."}</p> }>
+                {move || {
+                    leaderboard.read(cx).map(|leaderboard| match leaderboard {
+                        Ok(leaderboard) => view! { cx,
+                            <table>
+                                {leaderboard
+                                    .scores
+                                    .into_iter()
+                                    .map(|score| view! { cx,
+                                        <tr>
+                                            <td style="width: 2em; text-align: right">
+                                                {format!("{}.", score.rank)}
+                                            </td>
+                                            <td style="width: 8em">
+                                                <a href=format!("/stats/{}", score.name)>{score.name}</a>
+                                            </td>
+                                            <td style="width: 4em; text-align: right">
+                                                {format_time(score.time)}
+                                            </td>
+                                            <td style="width: 1em">
+                                                {score.has_replay.then(|| view! { cx,
+                                                    <a href=format!("/replay/{}", score.id) title="Watch replay">
+                                                        {"▶"}
+                                                    </a>
+                                                })}
+                                            </td>
+                                        </tr>
+                                    })
+                                    .collect::<Vec<_>>()}
+                            </table>
                         }
-                    }
-                />
-            </table>
+                        .into_view(cx),
+                        Err(e) => view! { cx, <p class="error">{e.to_string()}</p> }.into_view(cx),
+                    })
+                }}
+            </Suspense>
+            <div class="leaderboard-pages">
+                <button
+                    disabled=move || page() == 0
+                    on:click=move |_| set_page.update(|page| *page = page.saturating_sub(1))
+                >
+                    {"Prev"}
+                </button>
+                <span>
+                    {move || {
+                        page_count().map(|count| format!("Page {} of {}", page() + 1, count))
+                    }}
+                </span>
+                <button
+                    disabled=move || page_count().map_or(true, |count| page() + 1 >= count)
+                    on:click=move |_| set_page.update(|page| *page += 1)
+                >
+                    {"Next"}
+                </button>
+            </div>
         </div>
     }
-    /*
-
-    <div className="Leaderboard">
-      <h4>{difficulty}</h4>
-      <table>
-        <tbody>
-          {scores?.times
-            .filter((_, idx) => idx < 5)
-            .map((score, idx) => (
-              <tr key={idx}>
-                <td style={{ width: "1em", textAlign: "left" }}>{`${idx + 1}.`}</td>
-                <td style={{ width: "8em" }}>{truncateStr(score.name, 15)}</td>
-                <td style={{ width: "2em", textAlign: "right" }}>{score.time}</td>
-              </tr>
-            ))}
-        </tbody>
-      </table>
-    </div>
-    */
 }
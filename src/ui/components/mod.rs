@@ -1,5 +1,12 @@
+pub mod account;
+mod board_files;
 mod cell;
+pub mod daily;
 pub mod game;
 mod scoreboard;
 mod settings;
 mod leaderboards;
+pub mod replay;
+pub mod session;
+mod snapshot;
+pub mod stats;
@@ -0,0 +1,284 @@
+use crate::minesweeper::{Difficulty, Hint, Pos, Replay, Setting};
+use crate::ui::components::cell::*;
+use crate::ui::shared::{GameUpdater, MouseBindings, MouseButtons, CELL_SIZE};
+use leptos::leptos_dom::helpers::IntervalHandle;
+use leptos::*;
+use leptos_meta::Title;
+use leptos_router::use_params_map;
+
+/// How often playback advances, in milliseconds.
+const TICK: u32 = 50;
+const SPEEDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+
+#[component]
+pub fn ReplayViewer(cx: Scope) -> impl IntoView {
+    let params = use_params_map(cx);
+    let id = move || {
+        params.with(|p| {
+            p.get("id")
+                .and_then(|id| id.parse::<i64>().ok())
+                .unwrap_or_default()
+        })
+    };
+    let replay = create_resource(cx, id, move |id| get_replay(cx, id));
+
+    view! { cx,
+        <Title text="Minesweeper Replay" />
+
+        <div class="game">
+            <Suspense fallback=move || view! { cx, <p>{"Loading replay..."}</p> }>
+                {move || {
+                    replay.read(cx).map(|replay| match replay {
+                        Ok(replay) => view! { cx, <ReplayPlayer replay /> }.into_view(cx),
+                        Err(e) => view! { cx, <p class="error">{e.to_string()}</p> }.into_view(cx),
+                    })
+                }}
+            </Suspense>
+            <a href="/">{"Back to game"}</a>
+        </div>
+    }
+}
+
+/// Plays `replay` back on a board of its own, with playback controls.
+#[component]
+pub fn ReplayPlayer(cx: Scope, replay: Replay) -> impl IntoView {
+    let (game, set_game) = create_signal(cx, replay.game_at(0));
+    let (active_pos, set_active_pos) = create_signal::<Vec<Pos>>(cx, Vec::new());
+    let (mouse_down, set_mouse_down) = create_signal::<MouseButtons>(cx, MouseButtons::None);
+    let (setting, set_setting) = create_signal(
+        cx,
+        Setting {
+            difficulty: Difficulty::Custom,
+            width: replay.width,
+            height: replay.height,
+            mine_count: replay.mine_count,
+            first_click: replay.first_click,
+            question_marks: replay.question_marks,
+        },
+    );
+    let (time, set_time) = create_signal::<u16>(cx, 0);
+    let (hint, set_hint) = create_signal::<Option<Hint>>(cx, None);
+    let (ranked, set_ranked) = create_signal(cx, false);
+    let (session, set_session) = create_signal::<Option<String>>(cx, None);
+    let (cursor, set_cursor) = create_signal::<Option<Pos>>(cx, None);
+    let (flag_mode, set_flag_mode) = create_signal(cx, false);
+    let (bindings, set_bindings) = create_signal(cx, MouseBindings::default());
+    let (step, set_step) = create_signal(cx, 0usize);
+    let (clock, set_clock) = create_signal::<u32>(cx, 0);
+    let (speed, set_speed) = create_signal(cx, 1.0);
+    let (playing, set_playing) = create_signal(cx, false);
+
+    let duration = replay.duration();
+    let event_count = replay.events.len();
+    let replay = store_value(cx, replay);
+    let interval = store_value::<Option<IntervalHandle>>(cx, None);
+
+    provide_context(
+        cx,
+        GameUpdater {
+            game,
+            set_game,
+            setting,
+            set_setting,
+            time,
+            set_time,
+            hint,
+            set_hint,
+            cursor,
+            set_cursor,
+            flag_mode,
+            set_flag_mode,
+            bindings,
+            set_bindings,
+            ranked,
+            set_ranked,
+            session,
+            set_session,
+        },
+    );
+
+    let seek = move |clock_ms: u32| {
+        let current = step.get_untracked();
+        let target = replay.with_value(|r| {
+            r.events
+                .iter()
+                .take_while(|event| event.time <= clock_ms)
+                .count()
+        });
+        if target < current {
+            set_game(replay.with_value(|r| r.game_at(target)));
+        } else if target > current {
+            replay.with_value(|r| {
+                set_game.update(|g| {
+                    r.events[current..target]
+                        .iter()
+                        .for_each(|event| g.apply_at(event.action, event.time as f64))
+                })
+            });
+        }
+        set_step(target);
+        set_clock(clock_ms);
+        set_time((clock_ms / 1000) as u16);
+    };
+
+    let stop = move || {
+        interval.update_value(|i| {
+            if let Some(handle) = i.take() {
+                handle.clear();
+            }
+        });
+        set_playing(false);
+    };
+
+    let tick = move || {
+        let elapsed = (TICK as f64 * speed.get_untracked()) as u32;
+        seek(clock.get_untracked() + elapsed);
+        if step.get_untracked() == event_count {
+            stop();
+        }
+    };
+
+    let toggle_play = move |_| {
+        if playing() {
+            stop();
+            return;
+        }
+        if step() == event_count {
+            seek(0);
+        }
+        set_playing(true);
+        let handle = set_interval_with_handle(tick, std::time::Duration::from_millis(TICK as u64));
+        interval.update_value(|i| *i = handle.ok());
+    };
+
+    let step_forward = move |_| {
+        let next = replay.with_value(|r| r.events.get(step()).map(|event| event.time));
+        if let Some(time) = next {
+            seek(time);
+        }
+    };
+
+    let restart = move |_| {
+        stop();
+        seek(0);
+    };
+
+    let change_speed = move |ev| {
+        if let Ok(speed) = event_target_value(&ev).parse::<f64>() {
+            set_speed(speed);
+        }
+    };
+
+    on_cleanup(cx, stop);
+
+    let board_rows = move || game.with(|g| (0..g.board.height).collect::<Vec<u8>>());
+    let row_pos = move |row| {
+        game.with(|g| {
+            (0..g.board.width)
+                .map(|col| Pos { row, col })
+                .collect::<Vec<Pos>>()
+        })
+    };
+    let style = move || {
+        game.with(|g| {
+            format!(
+                "height: {}px; width: {}px",
+                g.board.height as usize * CELL_SIZE,
+                g.board.width as usize * CELL_SIZE
+            )
+        })
+    };
+    let progress = move || {
+        format!(
+            "{:.1} / {:.1}",
+            clock() as f64 / 1000.0,
+            duration as f64 / 1000.0
+        )
+    };
+
+    view! { cx,
+        <div class="ReplayControls">
+            <button on:click=restart>{"⏮"}</button>
+            <button on:click=toggle_play>{move || if playing() { "⏸" } else { "▶" }}</button>
+            <button on:click=step_forward>{"⏭"}</button>
+            <select on:change=change_speed>
+                {SPEEDS
+                    .iter()
+                    .map(|&s| view! { cx,
+                        <option value=s.to_string() selected={s == 1.0}>{format!("{}x", s)}</option>
+                    })
+                    .collect::<Vec<_>>()}
+            </select>
+            <span class="Progress">{progress}</span>
+        </div>
+        <div class="Board" role="grid" aria-label="Replay board" aria-readonly="true" style=style>
+            <For
+                each=board_rows
+                key=|&row| row
+                view=move |cx, row| {
+                    view! { cx,
+                        <div class="Row" role="row">
+                            <For
+                                each=move || row_pos(row)
+                                key=|&pos| pos.key()
+                                view=move |cx, pos| {
+                                    view! { cx,
+                                        <Cell
+                                            pos
+                                            active_pos
+                                            set_active_pos
+                                            mouse_down
+                                            set_mouse_down
+                                            readonly=true
+                                        />
+                                    }
+                                }
+                            />
+                        </div>
+                    }
+                }
+            />
+        </div>
+    }
+}
+
+#[server(GetReplay, "/api")]
+pub async fn get_replay(cx: Scope, id: i64) -> Result<Replay, ServerFnError> {
+    use crate::AppState;
+    use actix_web::{web, HttpRequest};
+
+    let req =
+        use_context::<HttpRequest>(cx).ok_or(ServerFnError::ServerError("no request".into()))?;
+
+    let app_state = req
+        .app_data::<web::Data<AppState>>()
+        .ok_or(ServerFnError::ServerError("no app state".into()))?;
+    let db = &app_state.db_pool;
+
+    let score = sqlx::query!(
+        r#"
+        SELECT
+            replay,
+            challenge_date IS NOT NULL AND challenge_date >= date('now') AS "hidden!: bool"
+        FROM
+            score
+        WHERE
+            id = ?
+        "#,
+        id
+    )
+    .fetch_one(db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+    // A replay of today's daily challenge would give its board away.
+    if score.hidden {
+        return Err(ServerFnError::Args(
+            "daily challenge replays show once the day is over".into(),
+        ));
+    }
+
+    let replay = score
+        .replay
+        .ok_or(ServerFnError::ServerError("no replay recorded".into()))?;
+    serde_json::from_str(&replay).map_err(|msg| ServerFnError::Deserialization(msg.to_string()))
+}
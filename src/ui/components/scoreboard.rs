@@ -1,27 +1,22 @@
 use leptos::*;
 
 use crate::{
-    minesweeper::{GameState, Minesweeper},
-    ui::shared::GameUpdater,
+    minesweeper::{GameState, Metrics},
+    ui::shared::{format_time, GameUpdater},
 };
 
 #[component]
 pub fn Scoreboard(cx: Scope) -> impl IntoView {
-    let GameUpdater {
-        game,
-        time,
-        setting,
-        ..
-    } = use_context(cx).unwrap();
+    let GameUpdater { game, time, .. } = use_context(cx).unwrap();
 
     let flags_remaining = move || {
         game.with(|g| {
-            setting().mine_count
+            g.mine_count as isize
                 - g.board
                     .iter()
                     .flatten()
                     .filter(|kind| kind.is_flagged())
-                    .count()
+                    .count() as isize
         })
     };
 
@@ -30,27 +25,139 @@ pub fn Scoreboard(cx: Scope) -> impl IntoView {
             <div class="Counter">{ flags_remaining }</div>
             <MinesweeperGuy />
             <div class="Counter">{ time }</div>
+            <HintButton />
+            <FlagModeButton />
         </div>
     }
 }
 
+/// Says when the board isn't dealt the way the settings ask, e.g. when no
+/// board without guessing could be found.
 #[component]
-fn MinesweeperGuy(cx: Scope) -> impl IntoView {
+pub fn DealtNotice(cx: Scope) -> impl IntoView {
+    let GameUpdater { game, setting, .. } = use_context(cx).unwrap();
+
+    move || {
+        let (state, dealt) = game.with(|g| (g.state, g.first_click));
+        (state != GameState::Unstarted && dealt != setting.with(|s| s.first_click)).then(|| {
+            view! { cx,
+                <p class="DealtNotice">{format!("This board was dealt with the {} policy.", dealt.to_string().to_lowercase())}</p>
+            }
+        })
+    }
+}
+
+/// What the last hint is worth, so a guess isn't taken for a sure thing. It
+/// goes once the hinted cell is opened.
+#[component]
+pub fn HintNotice(cx: Scope) -> impl IntoView {
+    let GameUpdater { game, hint, .. } = use_context(cx).unwrap();
+
+    move || {
+        hint()
+            .filter(|hint| !game.with(|g| g.get_cell(hint.pos).kind.is_open()))
+            .map(|hint| {
+                view! { cx,
+                    <p class="HintNotice">{format!("Hint: {}.", hint)}</p>
+                }
+            })
+    }
+}
+
+/// Says an unranked win stays off the leaderboards, which only take games
+/// the server dealt and timed.
+#[component]
+pub fn UnrankedNotice(cx: Scope) -> impl IntoView {
+    let GameUpdater { game, session, .. } = use_context(cx).unwrap();
+
+    move || {
+        (game.with(|g| g.state) == GameState::Win && session.get().is_none()).then(|| {
+            view! { cx,
+                <p class="UnrankedNotice">{"Unranked game: only ranked wins make the leaderboards."}</p>
+            }
+        })
+    }
+}
+
+/// How efficiently the last game was won.
+#[component]
+pub fn MetricsPanel(cx: Scope, metrics: ReadSignal<Option<Metrics>>) -> impl IntoView {
+    move || {
+        metrics().map(|m| {
+            view! { cx,
+                <table class="MetricsPanel">
+                    <tr>
+                        <td>{"Time"}</td><td>{format_time(m.time as i64)}</td>
+                    </tr>
+                    <tr>
+                        <td>{"3BV"}</td><td>{m.bbbv}</td>
+                        <td>{"3BV/s"}</td><td>{format!("{:.2}", m.bbbv_per_second())}</td>
+                    </tr>
+                    <tr>
+                        <td>{"Clicks"}</td>
+                        <td title="Left / right / chord">
+                            {format!("{} / {} / {}", m.clicks.left, m.clicks.right, m.clicks.chord)}
+                        </td>
+                        <td>{"IOE"}</td><td>{format!("{:.2}", m.ioe())}</td>
+                    </tr>
+                    <tr>
+                        <td>{"ZiNi"}</td><td>{m.zini}</td>
+                        <td>{"ZiNi/click"}</td><td>{format!("{:.2}", m.zini_efficiency())}</td>
+                    </tr>
+                </table>
+            }
+        })
+    }
+}
+
+#[component]
+fn HintButton(cx: Scope) -> impl IntoView {
+    let updater: GameUpdater = use_context(cx).unwrap();
+
+    let hints_used = move || updater.game.with(|g| g.hints_used);
+
+    view! { cx,
+        <button type="button" class="HintButton" title="Hint (?)" aria-label="Hint" on:click=move |_| updater.request_hint()>
+            {"💡"}{hints_used}
+        </button>
+    }
+}
+
+/// Switches taps between opening and flagging, for touch screens.
+#[component]
+fn FlagModeButton(cx: Scope) -> impl IntoView {
     let GameUpdater {
-        game,
-        set_game,
-        setting,
+        flag_mode,
+        set_flag_mode,
         ..
     } = use_context(cx).unwrap();
 
-    let guy = move || game.with(|g| g.state.as_emoji());
+    view! { cx,
+        <button
+            type="button"
+            class="FlagModeButton"
+            class:on=flag_mode
+            title="Flag mode"
+            aria-pressed=move || flag_mode().to_string()
+            on:click=move |_| set_flag_mode.update(|on| *on = !*on)
+        >
+            {"🚩"}
+        </button>
+    }
+}
+
+#[component]
+fn MinesweeperGuy(cx: Scope) -> impl IntoView {
+    let updater: GameUpdater = use_context(cx).unwrap();
 
-    let restart_game = move |_| set_game(Minesweeper::from_setting(setting.get()));
+    let guy = move || updater.game.with(|g| g.state.as_emoji());
+
+    let restart_game = move |_| updater.new_game(cx);
 
     view! { cx,
-        <div class="MinesweeperGuy" on:click=restart_game>
+        <button type="button" class="MinesweeperGuy" aria-label="New game" on:click=restart_game>
             {guy}
-        </div>
+        </button>
     }
 }
 
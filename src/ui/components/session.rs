@@ -0,0 +1,243 @@
+use crate::minesweeper::{Action, Metrics, PlayerView, Setting};
+use crate::ui::components::game::ScoreRejection;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::minesweeper::Minesweeper;
+        use std::collections::HashMap;
+        use std::sync::{Arc, Mutex};
+        use std::time::{Duration, Instant};
+
+        /// Sessions nobody has touched for this long are dropped.
+        const SESSION_TTL: Duration = Duration::from_secs(60 * 60);
+        /// Smallest custom board that's ranked, in cells. Smaller ones are
+        /// won in a click or two.
+        const MIN_RANKED_CELLS: usize = 64;
+        /// Fewest mines per hundred cells on a ranked custom board, a bit
+        /// under beginner's density.
+        const MIN_RANKED_DENSITY: usize = 10;
+
+        /// A ranked game held by the server. The client only ever sees its
+        /// [`PlayerView`], and action times come from the server's clock.
+        pub struct GameSession {
+            game: Minesweeper,
+            setting: Setting,
+            /// Day of the daily challenge the game is played for, if any.
+            challenge_date: Option<String>,
+            created: Instant,
+            last_action: Instant,
+        }
+
+        /// Each session has a lock of its own, so a slow action (dealing a
+        /// no-guess board) only holds up that game.
+        pub type GameSessions = Mutex<HashMap<String, Arc<Mutex<GameSession>>>>;
+
+        /// Holds `game` on the server and returns the session's id.
+        pub(crate) fn start_session(
+            cx: Scope,
+            game: Minesweeper,
+            setting: Setting,
+            challenge_date: Option<String>,
+        ) -> Result<String, ServerFnError> {
+            let id = format!("{:032x}", rand::random::<u128>());
+            let app_state = app_state(cx)?;
+            let mut sessions = app_state.sessions.lock().unwrap();
+            // Sessions busy with an action are in use, so they stay.
+            sessions.retain(|_, session| {
+                session
+                    .try_lock()
+                    .map_or(true, |session| session.last_action.elapsed() < SESSION_TTL)
+            });
+            sessions.insert(
+                id.clone(),
+                Arc::new(Mutex::new(GameSession {
+                    game,
+                    setting,
+                    challenge_date,
+                    created: Instant::now(),
+                    last_action: Instant::now(),
+                })),
+            );
+            Ok(id)
+        }
+
+        fn find_session(cx: Scope, id: &str) -> Result<Arc<Mutex<GameSession>>, ServerFnError> {
+            app_state(cx)?
+                .sessions
+                .lock()
+                .unwrap()
+                .get(id)
+                .cloned()
+                .ok_or(ServerFnError::ServerError("no such session".into()))
+        }
+
+        /// The board a ranked game on `setting` is played on. Only custom
+        /// boards take their size from the client.
+        fn ranked_setting(setting: Setting) -> Result<Setting, ServerFnError> {
+            use crate::minesweeper::{Difficulty, SETTINGS};
+
+            if setting.difficulty == Difficulty::Custom {
+                let cells = setting.width as usize * setting.height as usize;
+                if setting.mine_count >= cells {
+                    return Err(ServerFnError::Args("custom boards need a free cell".into()));
+                }
+                let sparse = setting.mine_count * 100 < cells * MIN_RANKED_DENSITY;
+                if cells < MIN_RANKED_CELLS || sparse {
+                    return Err(ServerFnError::Args(format!(
+                        "custom boards are ranked from {} cells and {}% mines",
+                        MIN_RANKED_CELLS, MIN_RANKED_DENSITY
+                    )));
+                }
+                return Ok(setting);
+            }
+            let standard = SETTINGS
+                .iter()
+                .find(|s| s.difficulty == setting.difficulty)
+                .ok_or(ServerFnError::Args(format!(
+                    "{} games aren't ranked",
+                    setting.difficulty
+                )))?;
+            Ok(Setting {
+                first_click: setting.first_click,
+                question_marks: setting.question_marks,
+                ..*standard
+            })
+        }
+
+        fn app_state(cx: Scope) -> Result<actix_web::web::Data<crate::AppState>, ServerFnError> {
+            use actix_web::{web, HttpRequest};
+
+            let req = use_context::<HttpRequest>(cx)
+                .ok_or(ServerFnError::ServerError("no request".into()))?;
+            req.app_data::<web::Data<crate::AppState>>()
+                .cloned()
+                .ok_or(ServerFnError::ServerError("no app state".into()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStart {
+    pub id: String,
+    pub view: PlayerView,
+}
+
+/// Deals a ranked game on `setting`. The board's size only comes from the
+/// client for custom boards.
+#[server(NewSession, "/api", "Cbor")]
+pub async fn new_session(cx: Scope, setting: Setting) -> Result<SessionStart, ServerFnError> {
+    let setting = ranked_setting(setting)?;
+    let game = Minesweeper::from_setting(setting);
+    let view = game.player_view();
+    let id = start_session(cx, game, setting, None)?;
+
+    Ok(SessionStart { id, view })
+}
+
+/// Applies `action` to the session's game and returns what the player can now
+/// see. Lost games are dropped; won games are kept until they're submitted.
+#[server(SessionAction, "/api", "Cbor")]
+pub async fn session_action(
+    cx: Scope,
+    id: String,
+    action: Action,
+) -> Result<PlayerView, ServerFnError> {
+    use crate::minesweeper::GameState;
+    use crate::ui::components::stats::insert_game;
+    use actix_web::{web, HttpRequest};
+
+    let session = find_session(cx, &id)?;
+    // The first click can deal a no-guess board, which takes a while, so the
+    // game is played on a blocking thread rather than the server's.
+    let (view, lost) = web::block(move || {
+        let mut session = session.lock().unwrap();
+        let mut lost = None;
+        if !session.game.state.is_over() {
+            let now = session.created.elapsed().as_secs_f64() * 1000.0;
+            session.game.apply_at(action, now);
+            session.last_action = Instant::now();
+            if session.game.state == GameState::Lose {
+                let game = &session.game;
+                lost = Some((session.setting.difficulty, game.replay(), game.clicks));
+            }
+        }
+        (session.game.player_view(), lost)
+    })
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+
+    if let Some((difficulty, replay, clicks)) = lost {
+        app_state(cx)?.sessions.lock().unwrap().remove(&id);
+        let req = use_context::<HttpRequest>(cx)
+            .ok_or(ServerFnError::ServerError("no request".into()))?;
+        insert_game(&req, difficulty, &replay, clicks).await?;
+    }
+    Ok(view)
+}
+
+/// Saves a won session's score, timed by the server, and returns how
+/// efficiently it was played. Games won faster than anyone could play them
+/// are turned away. Without a login the session stays, to be submitted again
+/// once there is one.
+#[server(SubmitSession, "/api")]
+pub async fn submit_session(
+    cx: Scope,
+    id: String,
+) -> Result<Result<Metrics, ScoreRejection>, ServerFnError> {
+    use crate::minesweeper::GameState;
+    use crate::ui::components::account::current_player;
+    use crate::ui::components::game::insert_score;
+    use crate::ui::components::stats::insert_game;
+    use actix_web::HttpRequest;
+
+    let req =
+        use_context::<HttpRequest>(cx).ok_or(ServerFnError::ServerError("no request".into()))?;
+    let (difficulty, replay, clicks, metrics, challenge_date) = {
+        let session = find_session(cx, &id)?;
+        let session = session.lock().unwrap();
+        if session.game.state != GameState::Win {
+            return Err(ServerFnError::ServerError("game isn't won".into()));
+        }
+        let metrics = session
+            .game
+            .metrics()
+            .ok_or(ServerFnError::ServerError("game isn't won".into()))?;
+        (
+            session.setting.difficulty,
+            session.game.replay(),
+            session.game.clicks,
+            metrics,
+            session.challenge_date.clone(),
+        )
+    };
+    // Checked while the session is still there to come back to.
+    if current_player(&req).await?.is_none() {
+        return Ok(Err(ScoreRejection::LoggedOut));
+    }
+    // Whoever takes the session out gets to submit it.
+    if app_state(cx)?
+        .sessions
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .is_none()
+    {
+        return Err(ServerFnError::ServerError("no such session".into()));
+    }
+
+    if let Err(e) = replay.check_pace() {
+        return Ok(Err(ScoreRejection::InvalidReplay(e)));
+    }
+    insert_game(&req, difficulty, &replay, clicks).await?;
+    insert_score(
+        &req,
+        difficulty,
+        &replay,
+        &metrics,
+        challenge_date.as_deref(),
+    )
+    .await?;
+    Ok(Ok(metrics))
+}
@@ -1,7 +1,8 @@
 use std::borrow::Cow;
 
-use crate::minesweeper::{Difficulty, Minesweeper, CUSTOM, SETTINGS};
-use crate::ui::shared::GameUpdater;
+use crate::minesweeper::{Difficulty, FirstClickPolicy, Setting, CUSTOM, SETTINGS};
+use crate::ui::components::account::save_mouse_bindings;
+use crate::ui::shared::{ChordStyle, GameUpdater, MouseBindings};
 use leptos::*;
 
 enum SettingField {
@@ -29,48 +30,70 @@ impl IntoAttribute for Difficulty {
 
 #[component]
 pub fn SettingsPanel(cx: Scope) -> impl IntoView {
+    let updater: GameUpdater = use_context(cx).unwrap();
     let GameUpdater {
-        set_game,
         setting,
         set_setting,
+        ranked,
+        set_ranked,
         ..
-    } = use_context(cx).unwrap();
+    } = updater;
     let (custom_setting, set_custom_setting) = create_signal(cx, CUSTOM);
 
-    let mode_select = move |ev, setting| {
+    let mode_select = move |ev, opt: Setting| {
         if event_target_checked(&ev) {
-            set_setting(setting);
+            set_setting(Setting {
+                first_click: setting.get().first_click,
+                question_marks: setting.get().question_marks,
+                ..opt
+            });
         }
     };
 
     let update_custom_field = move |ev, field| {
-        let setting = custom_setting.get();
+        let current = custom_setting.get();
         match field {
             SettingField::Width => {
                 let num = event_target_value(&ev)
                     .parse::<u8>()
-                    .unwrap_or(setting.width);
+                    .unwrap_or(current.width);
                 set_custom_setting.update(|setting| setting.width = num);
             }
             SettingField::Height => {
                 let num = event_target_value(&ev)
                     .parse::<u8>()
-                    .unwrap_or(setting.height);
+                    .unwrap_or(current.height);
                 set_custom_setting.update(|setting| setting.height = num);
             }
             SettingField::MineCount => {
                 let num = event_target_value(&ev)
                     .parse::<usize>()
-                    .unwrap_or(setting.mine_count);
-                if num < (setting.width * setting.height) as usize {
+                    .unwrap_or(current.mine_count);
+                if num < (current.width * current.height) as usize {
                     set_custom_setting.update(|setting| setting.mine_count = num)
                 }
             }
         }
-        set_setting(custom_setting.get());
+        set_setting(Setting {
+            first_click: setting.get().first_click,
+            question_marks: setting.get().question_marks,
+            ..custom_setting.get()
+        });
+    };
+
+    // Like the first-click policy, it's part of how a game is played, so it
+    // takes effect from the next game and replays stay true to theirs.
+    let toggle_question_marks = move |ev| {
+        let on = event_target_checked(&ev);
+        set_setting.update(|setting| setting.question_marks = on);
+    };
+
+    let toggle_ranked = move |ev| {
+        set_ranked(event_target_checked(&ev));
+        updater.new_game(cx);
     };
 
-    let new_game = move |_| set_game(Minesweeper::from_setting(setting.get()));
+    let new_game = move |_| updater.new_game(cx);
 
     view! { cx,
         <>
@@ -154,7 +177,131 @@ pub fn SettingsPanel(cx: Scope) -> impl IntoView {
                   </tbody>
                 </table>
             </div>
+            <label class="first-click">
+                {"First click: "}
+                <FirstClickSelect
+                    selected=Signal::derive(cx, move || setting().first_click)
+                    on_select=move |policy| set_setting.update(|setting| setting.first_click = policy)
+                />
+            </label>
+            <label class="question-marks" title="Flagging a flag marks it with a question mark, from the next game on">
+                <input
+                    type="checkbox"
+                    prop:checked={move || setting().question_marks}
+                    on:change=toggle_question_marks
+                />
+                {"Question marks"}
+            </label>
+            <label class="ranked" title="Boards are dealt and timed by the server; only ranked wins make the leaderboards. Custom boards are ranked from 64 cells and 10% mines">
+                <input
+                    type="checkbox"
+                    prop:checked=ranked
+                    on:change=toggle_ranked
+                />
+                {"Ranked"}
+            </label>
             <button on:click=new_game>{"New Game"}</button>
+            <MouseSettings />
         </>
     }
 }
+
+/// A select for the first-click policy, showing `selected`.
+#[component]
+pub fn FirstClickSelect<F>(
+    cx: Scope,
+    #[prop(into)] selected: Signal<FirstClickPolicy>,
+    on_select: F,
+) -> impl IntoView
+where
+    F: Fn(FirstClickPolicy) + 'static,
+{
+    let select = move |ev| {
+        let policy = event_target_value(&ev)
+            .parse()
+            .ok()
+            .and_then(FirstClickPolicy::from_id);
+        if let Some(policy) = policy {
+            on_select(policy);
+        }
+    };
+
+    view! { cx,
+        <select on:change=select>
+            {FirstClickPolicy::ALL
+                .into_iter()
+                .map(|policy| view! { cx,
+                    <option
+                        value=policy.id()
+                        selected=move || selected.get() == policy
+                        prop:selected=move || selected.get() == policy
+                    >
+                        {policy.to_string()}
+                    </option>
+                })
+                .collect::<Vec<_>>()}
+        </select>
+    }
+}
+
+/// Picks the mouse bindings, which logged-in players keep on their account.
+#[component]
+fn MouseSettings(cx: Scope) -> impl IntoView {
+    let GameUpdater {
+        bindings,
+        set_bindings,
+        ..
+    } = use_context(cx).unwrap();
+
+    let update = move |change: &dyn Fn(&mut MouseBindings)| {
+        let mut new = bindings.get_untracked();
+        change(&mut new);
+        set_bindings(new);
+        spawn_local(async move {
+            if let Err(e) = save_mouse_bindings(cx, new).await {
+                log::warn!("couldn't save mouse bindings: {}", e);
+            }
+        });
+    };
+
+    let select_chord = move |ev| {
+        let chord = event_target_value(&ev)
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| ChordStyle::ALL.get(i).copied());
+        if let Some(chord) = chord {
+            update(&|bindings| bindings.chord = chord);
+        }
+    };
+    let toggle_swap = move |ev| {
+        let swap = event_target_checked(&ev);
+        update(&|bindings| bindings.swap_buttons = swap);
+    };
+
+    view! { cx,
+        <div class="MouseSettings">
+            <label>
+                {"Chord: "}
+                <select on:change=select_chord>
+                    {ChordStyle::ALL
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, chord)| view! { cx,
+                            <option value=i prop:selected={move || bindings().chord == chord}>
+                                {chord.to_string()}
+                            </option>
+                        })
+                        .collect::<Vec<_>>()}
+                </select>
+            </label>
+            <label title="Right click opens and left click flags">
+                <input
+                    type="checkbox"
+                    prop:checked={move || bindings().swap_buttons}
+                    on:change=toggle_swap
+                />
+                {"Swap buttons"}
+            </label>
+        </div>
+    }
+}
@@ -0,0 +1,46 @@
+use crate::minesweeper::Minesweeper;
+use crate::ui::shared::GameUpdater;
+use leptos::html::Textarea;
+use leptos::*;
+
+/// Copies the current board out as a snapshot string, or loads one pasted in.
+#[component]
+pub fn SnapshotPanel(cx: Scope) -> impl IntoView {
+    let updater: GameUpdater = use_context(cx).unwrap();
+    let GameUpdater { game, time, .. } = updater;
+    let (error, set_error) = create_signal::<Option<String>>(cx, None);
+    let text: NodeRef<Textarea> = create_node_ref(cx);
+
+    let export = move |_| {
+        let snapshot = game.with_untracked(|g| g.to_snapshot(time.get_untracked()));
+        if let Some(text) = text.get() {
+            text.set_value(&snapshot);
+            text.select();
+        }
+        set_error(None);
+    };
+
+    let import = move |_| {
+        let Some(text) = text.get() else {
+            return;
+        };
+        match Minesweeper::from_snapshot(&text.value()) {
+            Ok((restored, elapsed)) => {
+                updater.load_game(restored, elapsed);
+                set_error(None);
+            }
+            Err(e) => set_error(Some(e.to_string())),
+        }
+    };
+
+    view! { cx,
+        <div class="Snapshot">
+            <textarea node_ref=text rows="6" placeholder="Paste a snapshot to import it" />
+            <div>
+                <button on:click=export>{"Export"}</button>
+                <button on:click=import>{"Import"}</button>
+            </div>
+            {move || error().map(|e| view! { cx, <p class="error">{e}</p> })}
+        </div>
+    }
+}
@@ -0,0 +1,472 @@
+use crate::minesweeper::{Clicks, Difficulty, Replay};
+use crate::ui::shared::format_time;
+use leptos::*;
+use leptos_meta::Title;
+use leptos_router::use_params_map;
+use serde::{Deserialize, Serialize};
+
+const CHART_WIDTH: f64 = 400.0;
+const CHART_HEIGHT: f64 = 150.0;
+const RECENT_GAMES: usize = 10;
+const DIFFICULTIES: [Difficulty; 4] = [
+    Difficulty::Beginner,
+    Difficulty::Intermediate,
+    Difficulty::Expert,
+    Difficulty::Custom,
+];
+
+/// A finished game, won or lost.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub difficulty: Difficulty,
+    pub won: bool,
+    /// Milliseconds from the first click to the last.
+    pub time: i64,
+    pub bbbv: i64,
+    pub clicks: i64,
+    pub date: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub name: String,
+    /// Oldest first.
+    pub games: Vec<GameRecord>,
+}
+
+/// Totals for one difficulty.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DifficultyStats {
+    pub played: usize,
+    pub won: usize,
+    pub best: Option<i64>,
+    pub average: Option<f64>,
+}
+
+impl DifficultyStats {
+    pub fn win_rate(&self) -> f64 {
+        match self.played {
+            0 => 0.0,
+            played => self.won as f64 / played as f64,
+        }
+    }
+}
+
+impl PlayerStats {
+    pub fn difficulty(&self, difficulty: Difficulty) -> DifficultyStats {
+        let games: Vec<_> = self
+            .games
+            .iter()
+            .filter(|game| game.difficulty == difficulty)
+            .collect();
+        let times: Vec<_> = games
+            .iter()
+            .filter(|game| game.won)
+            .map(|game| game.time)
+            .collect();
+        DifficultyStats {
+            played: games.len(),
+            won: times.len(),
+            best: times.iter().copied().min(),
+            average: (!times.is_empty())
+                .then(|| times.iter().sum::<i64>() as f64 / times.len() as f64),
+        }
+    }
+
+    /// The current and the longest run of wins in a row.
+    pub fn streaks(&self) -> (usize, usize) {
+        self.games
+            .iter()
+            .fold((0, 0), |(current, longest), game| match game.won {
+                true => (current + 1, longest.max(current + 1)),
+                false => (0, longest),
+            })
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn difficulty_of(replay: &Replay) -> Difficulty {
+    use crate::minesweeper::SETTINGS;
+
+    SETTINGS
+        .iter()
+        .find(|s| {
+            (s.height, s.width, s.mine_count) == (replay.height, replay.width, replay.mine_count)
+        })
+        .map_or(Difficulty::Custom, |s| s.difficulty)
+}
+
+/// Adds a finished game to the logged-in player's history. Anonymous games
+/// aren't tracked.
+#[cfg(feature = "ssr")]
+pub(crate) async fn insert_game(
+    req: &actix_web::HttpRequest,
+    difficulty: Difficulty,
+    replay: &Replay,
+    clicks: Clicks,
+) -> Result<(), ServerFnError> {
+    use crate::minesweeper::GameState;
+    use crate::ui::components::account::current_player;
+    use crate::AppState;
+    use actix_web::web;
+
+    let Some(player) = current_player(req).await? else {
+        return Ok(());
+    };
+
+    let app_state = req
+        .app_data::<web::Data<AppState>>()
+        .ok_or(ServerFnError::ServerError("no app state".into()))?;
+    let db = &app_state.db_pool;
+    let player_id = player.id;
+
+    let game = replay.game_at(replay.events.len());
+    let difficulty_id = difficulty.id();
+    let first_click = replay.first_click.id();
+    let won = game.state == GameState::Win;
+    let time = replay.duration();
+    let seconds = time / 1000;
+    let bbbv = game.board.bbbv() as i64;
+    let clicks = clicks.total() as i64;
+
+    sqlx::query!(
+        "
+    INSERT INTO game (player_id, difficulty_id, first_click, won, time, time_ms, bbbv, clicks)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+    ",
+        player_id,
+        difficulty_id,
+        first_click,
+        won,
+        seconds,
+        time,
+        bbbv,
+        clicks
+    )
+    .execute(db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+
+    Ok(())
+}
+
+/// Records a game played in the browser once it's over. Restoring the replay
+/// checks its mines against its seed, and a win has to keep a human pace.
+#[server(RecordGame, "/api", "Cbor")]
+pub async fn record_game(cx: Scope, replay: Replay, clicks: Clicks) -> Result<(), ServerFnError> {
+    use crate::minesweeper::GameState;
+    use actix_web::HttpRequest;
+
+    let game = replay
+        .restore()
+        .map_err(|e| ServerFnError::Args(e.to_string()))?;
+    if !game.state.is_over() {
+        return Err(ServerFnError::Args("game isn't over".into()));
+    }
+    if game.state == GameState::Win {
+        replay
+            .check_pace()
+            .map_err(|e| ServerFnError::Args(e.to_string()))?;
+    }
+    if !clicks.covers(&replay.clicks()) {
+        return Err(ServerFnError::Args("clicks don't match the replay".into()));
+    }
+
+    let req =
+        use_context::<HttpRequest>(cx).ok_or(ServerFnError::ServerError("no request".into()))?;
+    insert_game(&req, difficulty_of(&replay), &replay, clicks).await
+}
+
+/// The history of player `name`, or of the logged-in player when there's no
+/// name.
+#[server(GetPlayerStats, "/api")]
+pub async fn get_player_stats(
+    cx: Scope,
+    name: Option<String>,
+) -> Result<PlayerStats, ServerFnError> {
+    use crate::ui::components::account::current_player;
+    use crate::AppState;
+    use actix_web::{web, HttpRequest};
+
+    let req =
+        use_context::<HttpRequest>(cx).ok_or(ServerFnError::ServerError("no request".into()))?;
+    let name = match name {
+        Some(name) => name,
+        None => {
+            current_player(&req)
+                .await?
+                .ok_or(ServerFnError::Args("log in to see your stats".into()))?
+                .name
+        }
+    };
+
+    let app_state = req
+        .app_data::<web::Data<AppState>>()
+        .ok_or(ServerFnError::ServerError("no app state".into()))?;
+    let db = &app_state.db_pool;
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT
+            g.difficulty_id AS "difficulty_id!",
+            g.won AS "won!: bool",
+            g.time_ms AS "time!",
+            g.bbbv AS "bbbv!",
+            g.clicks AS "clicks!",
+            date(g.finished_at) AS "date!: String"
+        FROM
+            game AS g
+        INNER JOIN player AS p ON p.id = g.player_id
+        WHERE
+            p.name = ?
+        ORDER BY
+            g.id
+        "#,
+        name
+    )
+    .fetch_all(db)
+    .await
+    .map_err(|msg| ServerFnError::ServerError(msg.to_string()))?;
+
+    let games = rows
+        .into_iter()
+        .map(|row| GameRecord {
+            difficulty: Difficulty::from_id(row.difficulty_id as u8).unwrap_or(Difficulty::Custom),
+            won: row.won,
+            time: row.time,
+            bbbv: row.bbbv,
+            clicks: row.clicks,
+            date: row.date,
+        })
+        .collect();
+    Ok(PlayerStats { name, games })
+}
+
+#[component]
+pub fn StatsPage(cx: Scope) -> impl IntoView {
+    let params = use_params_map(cx);
+    let name = move || params.with(|p| p.get("name").cloned());
+    let stats = create_resource(cx, name, move |name| get_player_stats(cx, name));
+
+    view! { cx,
+        <Title text="Minesweeper Stats" />
+
+        <div class="Stats">
+            <Suspense fallback=move || view! { cx, <p>{"Loading stats..."}</p> }>
+                {move || {
+                    stats.read(cx).map(|stats| match stats {
+                        Ok(stats) => view! { cx, <PlayerStatsView stats /> }.into_view(cx),
+                        Err(e) => view! { cx, <p class="error">{e.to_string()}</p> }.into_view(cx),
+                    })
+                }}
+            </Suspense>
+            <a href="/">{"Back to game"}</a>
+        </div>
+    }
+}
+
+#[component]
+fn PlayerStatsView(cx: Scope, stats: PlayerStats) -> impl IntoView {
+    let name = stats.name.clone();
+    let (current_streak, longest_streak) = stats.streaks();
+    let rows = DIFFICULTIES
+        .into_iter()
+        .map(|difficulty| (difficulty, stats.difficulty(difficulty)))
+        .filter(|(_, totals)| totals.played > 0)
+        .map(|(difficulty, totals)| {
+            view! { cx,
+                <tr>
+                    <td>{difficulty.to_string()}</td>
+                    <td>{totals.played}</td>
+                    <td>{totals.won}</td>
+                    <td>{format!("{:.0}%", totals.win_rate() * 100.0)}</td>
+                    <td>{totals.best.map_or("-".to_string(), format_time)}</td>
+                    <td>{totals.average.map_or("-".to_string(), |t| format_time(t as i64))}</td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+    let recent = stats
+        .games
+        .iter()
+        .rev()
+        .take(RECENT_GAMES)
+        .map(|game| {
+            view! { cx,
+                <tr>
+                    <td>{game.date.clone()}</td>
+                    <td>{game.difficulty.to_string()}</td>
+                    <td>{if game.won { "Won" } else { "Lost" }}</td>
+                    <td>{format_time(game.time)}</td>
+                    <td>{game.bbbv}</td>
+                    <td>{game.clicks}</td>
+                </tr>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! { cx,
+        <h3>{name}</h3>
+        <table class="StatsTable">
+            <thead>
+                <tr>
+                    <th></th>
+                    <th>{"Played"}</th>
+                    <th>{"Won"}</th>
+                    <th>{"Win rate"}</th>
+                    <th>{"Best"}</th>
+                    <th>{"Average"}</th>
+                </tr>
+            </thead>
+            <tbody>{rows}</tbody>
+        </table>
+        <p>{format!("Current streak: {}, longest streak: {}", current_streak, longest_streak)}</p>
+        <HistoryChart stats />
+        <table class="StatsTable">
+            <thead>
+                <tr>
+                    <th>{"Date"}</th>
+                    <th></th>
+                    <th></th>
+                    <th>{"Time"}</th>
+                    <th>{"3BV"}</th>
+                    <th>{"Clicks"}</th>
+                </tr>
+            </thead>
+            <tbody>{recent}</tbody>
+        </table>
+    }
+}
+
+/// Winning times in the order they were played, one line per difficulty.
+#[component]
+fn HistoryChart(cx: Scope, stats: PlayerStats) -> impl IntoView {
+    let slowest = stats
+        .games
+        .iter()
+        .filter(|game| game.won)
+        .map(|game| game.time)
+        .max()
+        .unwrap_or_default()
+        .max(1) as f64;
+    let step = CHART_WIDTH / stats.games.len().saturating_sub(1).max(1) as f64;
+
+    let lines = DIFFICULTIES
+        .into_iter()
+        .map(|difficulty| {
+            let points = stats
+                .games
+                .iter()
+                .enumerate()
+                .filter(|(_, game)| game.won && game.difficulty == difficulty)
+                .map(|(i, game)| {
+                    let y = CHART_HEIGHT - game.time as f64 / slowest * CHART_HEIGHT;
+                    format!("{:.1},{:.1}", i as f64 * step, y)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            view! { cx,
+                <polyline class=difficulty.to_string().to_lowercase() points=points />
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! { cx,
+        <svg
+            class="HistoryChart"
+            viewBox=format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)
+            preserveAspectRatio="none"
+        >
+            {lines}
+        </svg>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(difficulty: Difficulty, won: bool, time: i64) -> GameRecord {
+        GameRecord {
+            difficulty,
+            won,
+            time,
+            bbbv: 10,
+            clicks: 20,
+            date: "2023-07-15".into(),
+        }
+    }
+
+    fn history(results: &[bool]) -> PlayerStats {
+        PlayerStats {
+            name: "player".into(),
+            games: results
+                .iter()
+                .map(|&won| game(Difficulty::Beginner, won, 5000))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn loss_resets_the_current_streak() {
+        assert_eq!(history(&[true, true, true, false]).streaks(), (0, 3));
+        assert_eq!(history(&[true, true, false, true]).streaks(), (1, 2));
+    }
+
+    #[test]
+    fn longest_streak_runs_to_the_last_game() {
+        assert_eq!(history(&[true, false, true, true, true]).streaks(), (3, 3));
+        assert_eq!(history(&[true, true]).streaks(), (2, 2));
+    }
+
+    #[test]
+    fn no_games_no_streaks() {
+        assert_eq!(history(&[]).streaks(), (0, 0));
+    }
+
+    #[test]
+    fn difficulty_without_wins() {
+        let stats = PlayerStats {
+            name: "player".into(),
+            games: vec![
+                game(Difficulty::Expert, false, 3000),
+                game(Difficulty::Expert, false, 8000),
+                game(Difficulty::Beginner, true, 4000),
+            ],
+        };
+        let expert = stats.difficulty(Difficulty::Expert);
+        assert_eq!(
+            expert,
+            DifficultyStats {
+                played: 2,
+                won: 0,
+                best: None,
+                average: None,
+            }
+        );
+        assert_eq!(expert.win_rate(), 0.0);
+        assert_eq!(
+            stats.difficulty(Difficulty::Intermediate),
+            DifficultyStats::default()
+        );
+    }
+
+    #[test]
+    fn averages_only_count_wins() {
+        let stats = PlayerStats {
+            name: "player".into(),
+            games: vec![
+                game(Difficulty::Beginner, true, 4000),
+                game(Difficulty::Beginner, false, 1000),
+                game(Difficulty::Beginner, true, 6000),
+                game(Difficulty::Expert, true, 90_000),
+            ],
+        };
+        let beginner = stats.difficulty(Difficulty::Beginner);
+        assert_eq!(beginner.played, 3);
+        assert_eq!(beginner.won, 2);
+        assert_eq!(beginner.best, Some(4000));
+        assert_eq!(beginner.average, Some(5000.0));
+        assert!((beginner.win_rate() - 2.0 / 3.0).abs() < 1e-9);
+    }
+}
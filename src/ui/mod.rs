@@ -1,11 +1,17 @@
 mod components;
 mod shared;
+mod storage;
 
 use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
 
+pub use components::account::*;
+pub use components::daily::*;
 pub use components::game::*;
+pub use components::replay::*;
+pub use components::session::*;
+pub use components::stats::*;
 
 #[component]
 pub fn App(cx: Scope) -> impl IntoView {
@@ -21,12 +27,16 @@ pub fn App(cx: Scope) -> impl IntoView {
 
         // sets the document title
         <Title text="Welcome to Leptos"/>
+        <Meta name="viewport" content="width=device-width, initial-scale=1"/>
 
         // content for this welcome page
         <Router>
             <main>
                 <Routes>
                     <Route path="" view=|cx| view! { cx, <Game /> }/>
+                    <Route path="/replay/:id" view=|cx| view! { cx, <ReplayViewer /> }/>
+                    <Route path="/stats" view=|cx| view! { cx, <StatsPage /> }/>
+                    <Route path="/stats/:name" view=|cx| view! { cx, <StatsPage /> }/>
                 </Routes>
             </main>
         </Router>
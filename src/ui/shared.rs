@@ -1,5 +1,10 @@
-use crate::minesweeper::{Minesweeper, Setting};
-use leptos::{ReadSignal, WriteSignal};
+use crate::minesweeper::{Action, Hint, Minesweeper, Pos, Setting, CUSTOM, SETTINGS};
+use crate::ui::components::session::{new_session, session_action};
+use leptos::{
+    spawn_local, window, ReadSignal, Scope, SignalGetUntracked, SignalSet, SignalUpdate,
+    SignalWithUntracked, WriteSignal,
+};
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy)]
 pub struct GameUpdater {
@@ -8,6 +13,161 @@ pub struct GameUpdater {
     pub setting: ReadSignal<Setting>,
     pub set_setting: WriteSignal<Setting>,
     pub time: ReadSignal<u16>,
+    pub set_time: WriteSignal<u16>,
+    /// Cell suggested to open, highlighted until it's opened.
+    pub hint: ReadSignal<Option<Hint>>,
+    pub set_hint: WriteSignal<Option<Hint>>,
+    /// Cell the keyboard acts on, once the keyboard has been used.
+    pub cursor: ReadSignal<Option<Pos>>,
+    pub set_cursor: WriteSignal<Option<Pos>>,
+    /// Whether taps flag instead of open, for touch screens.
+    pub flag_mode: ReadSignal<bool>,
+    pub set_flag_mode: WriteSignal<bool>,
+    pub bindings: ReadSignal<MouseBindings>,
+    pub set_bindings: WriteSignal<MouseBindings>,
+    /// Whether new games are played on the server.
+    pub ranked: ReadSignal<bool>,
+    pub set_ranked: WriteSignal<bool>,
+    /// Id of the server-side session the current game is played in.
+    pub session: ReadSignal<Option<String>>,
+    pub set_session: WriteSignal<Option<String>>,
+}
+
+impl GameUpdater {
+    /// Starts a game with the current setting, on the server when ranked.
+    pub fn new_game(&self, cx: Scope) {
+        let setting = self.setting.get_untracked();
+        self.set_game.set(Minesweeper::from_setting(setting));
+        self.set_session.set(None);
+        if !self.ranked.get_untracked() {
+            return;
+        }
+
+        let GameUpdater {
+            set_game,
+            set_session,
+            ..
+        } = *self;
+        spawn_local(async move {
+            match new_session(cx, setting).await {
+                Ok(start) => {
+                    set_game.set(Minesweeper::from_player_view(start.view));
+                    set_session.set(Some(start.id));
+                }
+                Err(e) => log::warn!("couldn't start a ranked game: {}", e),
+            }
+        });
+    }
+
+    /// Swaps in a game loaded from outside, e.g. an imported board, with
+    /// `time` seconds on the timer. The setting follows the board's size.
+    pub fn load_game(&self, game: Minesweeper, time: u16) {
+        let board = (game.board.height, game.board.width, game.mine_count);
+        let setting = SETTINGS
+            .into_iter()
+            .find(|s| (s.height, s.width, s.mine_count) == board)
+            .unwrap_or(Setting {
+                height: board.0,
+                width: board.1,
+                mine_count: board.2,
+                ..CUSTOM
+            });
+        let setting = Setting {
+            first_click: game.first_click,
+            question_marks: game.question_marks,
+            ..setting
+        };
+        self.set_session.set(None);
+        self.set_hint.set(None);
+        self.set_setting.set(setting);
+        self.set_game.set(game);
+        self.set_time.set(time);
+    }
+
+    /// Plays `action`, locally or through the game's session.
+    pub fn act(&self, cx: Scope, action: Action) {
+        if !self.is_ranked_game() {
+            self.set_game.update(|game| game.apply_at(action, now()));
+            return;
+        }
+        let Some(id) = self.session.get_untracked() else {
+            return;
+        };
+
+        let GameUpdater {
+            set_game, session, ..
+        } = *self;
+        spawn_local(async move {
+            let view = session_action(cx, id.clone(), action).await;
+            // The player may have started another game in the meantime.
+            if session.get_untracked() != Some(id) {
+                return;
+            }
+            match view {
+                Ok(view) => set_game.set(Minesweeper::from_player_view(view)),
+                Err(e) => log::warn!("action failed: {}", e),
+            }
+        });
+    }
+
+    /// Moves the keyboard cursor by `rows` and `cols`, staying on the board.
+    /// The first move puts it in the top left corner.
+    pub fn move_cursor(&self, rows: i16, cols: i16) {
+        let (height, width) = self
+            .game
+            .with_untracked(|g| (g.board.height as i16, g.board.width as i16));
+        if height == 0 || width == 0 {
+            return;
+        }
+        let cursor = match self.cursor.get_untracked() {
+            Some(pos) => Pos {
+                row: (pos.row as i16 + rows).clamp(0, height - 1) as u8,
+                col: (pos.col as i16 + cols).clamp(0, width - 1) as u8,
+            },
+            None => Pos { row: 0, col: 0 },
+        };
+        self.set_cursor.set(Some(cursor));
+    }
+
+    /// Plays the action `to_action` makes of the cell under the keyboard
+    /// cursor.
+    pub fn act_at_cursor(&self, cx: Scope, to_action: fn(Pos) -> Action) {
+        let on_board = |pos: Pos| self.game.with_untracked(|g| g.board.get(pos).is_some());
+        if let Some(pos) = self.cursor.get_untracked().filter(|&pos| on_board(pos)) {
+            self.act(cx, to_action(pos));
+        }
+    }
+
+    pub fn request_hint(&self) {
+        if self.is_ranked_game() {
+            return;
+        }
+        let mut hint = None;
+        self.set_game.update(|game| hint = game.hint());
+        self.set_hint.set(hint);
+    }
+
+    pub fn undo(&self) {
+        if !self.is_ranked_game() {
+            self.set_game.update(|game| {
+                game.undo();
+            });
+        }
+    }
+
+    pub fn redo(&self) {
+        if !self.is_ranked_game() {
+            self.set_game.update(|game| {
+                game.redo_at(now());
+            });
+        }
+    }
+
+    /// Ranked games are waiting on or played through a session; they can't
+    /// be helped along locally.
+    pub fn is_ranked_game(&self) -> bool {
+        self.session.get_untracked().is_some() || self.ranked.get_untracked()
+    }
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -16,6 +176,7 @@ pub enum MouseButtons {
     LClick,
     RClick,
     LRClick,
+    Middle,
     Others(u16),
 }
 
@@ -26,9 +187,79 @@ impl MouseButtons {
             1 => Self::LClick,
             2 => Self::RClick,
             3 => Self::LRClick,
+            4 => Self::Middle,
             x => Self::Others(x),
         }
     }
 }
 
+/// How a chord is clicked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ChordStyle {
+    /// Left and right buttons together.
+    #[default]
+    BothButtons,
+    Middle,
+    /// The open button on a number, so the other button only flags.
+    ClickNumber,
+}
+
+impl ChordStyle {
+    pub const ALL: [ChordStyle; 3] = [
+        ChordStyle::BothButtons,
+        ChordStyle::Middle,
+        ChordStyle::ClickNumber,
+    ];
+}
+
+impl std::fmt::Display for ChordStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChordStyle::BothButtons => write!(f, "Left + right"),
+            ChordStyle::Middle => write!(f, "Middle click"),
+            ChordStyle::ClickNumber => write!(f, "Click a number"),
+        }
+    }
+}
+
+/// Which mouse buttons open, flag and chord.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MouseBindings {
+    pub chord: ChordStyle,
+    /// Right opens and left flags.
+    pub swap_buttons: bool,
+}
+
+impl MouseBindings {
+    /// The action `buttons` play on `pos`, which is an open cell when
+    /// `on_open`.
+    pub fn action(&self, buttons: MouseButtons, pos: Pos, on_open: bool) -> Option<Action> {
+        let buttons = match (buttons, self.swap_buttons) {
+            (MouseButtons::LClick, true) => MouseButtons::RClick,
+            (MouseButtons::RClick, true) => MouseButtons::LClick,
+            (buttons, _) => buttons,
+        };
+        match (buttons, self.chord) {
+            (MouseButtons::LClick, ChordStyle::ClickNumber) if on_open => Some(Action::Chord(pos)),
+            (MouseButtons::LClick, _) => Some(Action::Open(pos)),
+            (MouseButtons::RClick, _) => Some(Action::Flag(pos)),
+            (MouseButtons::LRClick, ChordStyle::BothButtons) => Some(Action::Chord(pos)),
+            (MouseButtons::Middle, ChordStyle::Middle) => Some(Action::Chord(pos)),
+            _ => None,
+        }
+    }
+}
+
 pub const CELL_SIZE: usize = 30;
+
+/// `ms` milliseconds as seconds with three decimals, the way times are shown.
+pub fn format_time(ms: i64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+/// Milliseconds from the browser's monotonic clock, used to timestamp actions.
+pub fn now() -> f64 {
+    window()
+        .performance()
+        .map_or(0.0, |performance| performance.now())
+}
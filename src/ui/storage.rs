@@ -0,0 +1,176 @@
+use crate::minesweeper::{Clicks, GameState, Minesweeper, PlayerView, Replay, Setting};
+use crate::ui::shared::{now, MouseBindings};
+use leptos::window;
+use serde::{Deserialize, Serialize};
+
+const SAVED_GAME_KEY: &str = "minesweeper-game";
+const BINDINGS_KEY: &str = "minesweeper-mouse-bindings";
+
+/// The game in progress, kept in localStorage so a reload picks it up again.
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    setting: Setting,
+    board: SavedBoard,
+    /// Seconds on the timer when saved.
+    time: u16,
+    /// `Date.now()` when saved, to count the time spent away.
+    saved_at: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SavedBoard {
+    Unstarted,
+    /// A local game, rebuilt from its replay on load. The replay's clock is
+    /// saved separately since it only means something on the page it ran on.
+    Local {
+        replay: Replay,
+        elapsed: f64,
+        hints_used: usize,
+        undos_used: usize,
+        #[serde(default)]
+        clicks: Clicks,
+    },
+    /// A game loaded from outside, which has no replay to rebuild it from.
+    Snapshot(String),
+    /// A game played through a server session.
+    Session {
+        id: String,
+        view: PlayerView,
+    },
+}
+
+/// A game read back from storage, with its clock running again.
+pub struct RestoredGame {
+    pub setting: Setting,
+    pub game: Minesweeper,
+    pub time: u16,
+    pub session: Option<String>,
+}
+
+fn storage() -> Option<web_sys::Storage> {
+    window().local_storage().ok().flatten()
+}
+
+pub fn save_game(setting: Setting, game: &Minesweeper, time: u16, session: Option<String>) {
+    let Some(storage) = storage() else {
+        return;
+    };
+    let board = match session {
+        Some(id) => SavedBoard::Session {
+            id,
+            view: game.player_view(),
+        },
+        None if game.restored => SavedBoard::Snapshot(game.to_snapshot(time)),
+        None if game.state == GameState::Unstarted => SavedBoard::Unstarted,
+        None => SavedBoard::Local {
+            replay: game.replay(),
+            elapsed: game.elapsed(now()).unwrap_or_default(),
+            hints_used: game.hints_used,
+            undos_used: game.undos_used,
+            clicks: game.clicks,
+        },
+    };
+    let saved = SavedGame {
+        setting,
+        board,
+        time,
+        saved_at: js_sys::Date::now(),
+    };
+    match serde_json::to_string(&saved) {
+        Ok(json) => _ = storage.set_item(SAVED_GAME_KEY, &json),
+        Err(e) => log::warn!("couldn't save game: {}", e),
+    }
+}
+
+/// Restores the saved game. Anything that doesn't hold up is thrown away so
+/// the page starts fresh instead.
+pub fn load_game() -> Option<RestoredGame> {
+    let storage = storage()?;
+    let json = storage.get_item(SAVED_GAME_KEY).ok().flatten()?;
+    let restored = restore(&json);
+    if let Err(e) = &restored {
+        log::warn!("discarding saved game: {}", e);
+        _ = storage.remove_item(SAVED_GAME_KEY);
+    }
+    restored.ok()
+}
+
+fn restore(json: &str) -> Result<RestoredGame, String> {
+    let saved: SavedGame = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let setting = saved.setting;
+    let away = (js_sys::Date::now() - saved.saved_at).max(0.0);
+
+    let (game, time, session) = match saved.board {
+        SavedBoard::Unstarted => (Minesweeper::from_setting(setting), 0, None),
+        SavedBoard::Local {
+            replay,
+            elapsed,
+            hints_used,
+            undos_used,
+            clicks,
+        } => {
+            if (replay.height, replay.width, replay.mine_count)
+                != (setting.height, setting.width, setting.mine_count)
+            {
+                return Err("board doesn't match the setting".into());
+            }
+            // Storage can be edited, so the layout has to be the seed's.
+            let mut game = replay.restore().map_err(|e| e.to_string())?;
+            game.hints_used = hints_used;
+            game.undos_used = undos_used;
+            game.clicks = clicks;
+            let time = match game.state {
+                GameState::Playing => {
+                    let elapsed = elapsed + away;
+                    game.resume_clock(now(), elapsed);
+                    (elapsed / 1000.0) as u16
+                }
+                _ => saved.time,
+            };
+            (game, time, None)
+        }
+        SavedBoard::Snapshot(snapshot) => {
+            let (game, time) = Minesweeper::from_snapshot(&snapshot).map_err(|e| e.to_string())?;
+            let time = match game.state {
+                GameState::Playing => time.saturating_add((away / 1000.0) as u16),
+                _ => time,
+            };
+            (game, time, None)
+        }
+        SavedBoard::Session { id, view } => {
+            if view.cells.len() != view.height as usize * view.width as usize {
+                return Err("board doesn't match its size".into());
+            }
+            let time = match view.state {
+                GameState::Playing => saved.time.saturating_add((away / 1000.0) as u16),
+                _ => saved.time,
+            };
+            (Minesweeper::from_player_view(view), time, Some(id))
+        }
+    };
+
+    Ok(RestoredGame {
+        setting,
+        game,
+        time,
+        session,
+    })
+}
+
+/// The mouse bindings picked on this browser, or the classic ones.
+pub fn load_bindings() -> MouseBindings {
+    storage()
+        .and_then(|storage| storage.get_item(BINDINGS_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_bindings(bindings: MouseBindings) {
+    let Some(storage) = storage() else {
+        return;
+    };
+    match serde_json::to_string(&bindings) {
+        Ok(json) => _ = storage.set_item(BINDINGS_KEY, &json),
+        Err(e) => log::warn!("couldn't save mouse bindings: {}", e),
+    }
+}